@@ -0,0 +1,462 @@
+//! Times every generator, building the cell graph, solving and rendering on square mazes from a
+//! fixed seed. Each benchmark is repeated up to `--samples` times (or until it has taken a couple
+//! of seconds) after a warm up run, and the fastest, median & mean times are reported.
+//!
+//! Building & searching the graph are timed three ways: a `hashed` baseline that searches the
+//! builder's hash maps with a `HashSet` of visited nodes & a `HashMap` of parents, the way `Graph`
+//! worked before it was compressed, the compressed `Graph` and the `IndexedGraph`, which keeps its
+//! search state in flat arrays. How much faster the last two are is printed after each size.
+//!
+//! Run with `cargo bench --bench suite`, sizes can be passed after `--`, e.g. `-- 100 1000`.
+//! `--csv results.csv` also writes the results as CSV to compare between runs, `--samples 5`
+//! changes the number of samples.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+use maze_v1::algo::{
+    grid::Grid, maze::MazeGenerate, ParallelTiled, RandomisedDFS, RandomisedKruskal,
+};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path, indexed::IndexedGraph};
+use maze_v1::render::RenderOptions;
+
+type Point = (usize, usize);
+type Adjacency = HashMap<Point, HashMap<Point, usize>>;
+
+const DEFAULT_SIZES: [usize; 5] = [100, 500, 1000, 2000, 5000];
+const DEFAULT_SAMPLES: usize = 10;
+const SEED: u64 = 1;
+/// Stop taking samples of a benchmark once it has run for this long
+const TIME_LIMIT: Duration = Duration::from_secs(2);
+
+struct Options {
+    sizes: Vec<usize>,
+    samples: usize,
+    csv: Option<String>,
+}
+
+impl Options {
+    /// Read the options from the command line, any size below 2 is an error as a maze that small
+    /// has nothing to solve
+    fn from_args() -> Result<Self, String> {
+        let mut options = Options {
+            sizes: vec![],
+            samples: DEFAULT_SAMPLES,
+            csv: None,
+        };
+        let mut args = std::env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--csv" => options.csv = args.next(),
+                "--samples" => {
+                    options.samples = args
+                        .next()
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or(DEFAULT_SAMPLES)
+                        .max(1)
+                }
+                arg => options.sizes.extend(arg.parse::<usize>().ok()),
+            }
+        }
+
+        if let Some(size) = options.sizes.iter().find(|size| **size < 2) {
+            return Err(format!(
+                "size {} is too small, mazes need at least 2x2 cells",
+                size
+            ));
+        }
+
+        if options.sizes.is_empty() {
+            options.sizes = DEFAULT_SIZES.to_vec();
+        }
+
+        Ok(options)
+    }
+}
+
+/// Timings of one benchmark at one size
+struct Measurement {
+    name: &'static str,
+    size: usize,
+    samples: Vec<Duration>,
+}
+
+impl Measurement {
+    fn min(&self) -> Duration {
+        self.samples[0]
+    }
+
+    fn median(&self) -> Duration {
+        self.samples[self.samples.len() / 2]
+    }
+
+    fn mean(&self) -> Duration {
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    fn max(&self) -> Duration {
+        self.samples[self.samples.len() - 1]
+    }
+}
+
+/// Run `f` once to warm up, then time it up to `samples` times. `setup` isn't timed, its result
+/// is handed to `f` for each sample. Whatever `f` returns is dropped after the timer stops, so
+/// return anything expensive to drop rather than dropping it inside `f`.
+fn bench<S, R>(
+    name: &'static str,
+    size: usize,
+    samples: usize,
+    mut setup: impl FnMut() -> S,
+    mut f: impl FnMut(S) -> R,
+) -> Measurement {
+    let started = Instant::now();
+    let mut times = Vec::<Duration>::with_capacity(samples);
+
+    drop(f(setup()));
+
+    while times.len() < samples && (times.is_empty() || started.elapsed() < TIME_LIMIT) {
+        let input = setup();
+        let now = Instant::now();
+        let result = f(input);
+        times.push(now.elapsed());
+        drop(result);
+    }
+
+    times.sort();
+
+    let measurement = Measurement {
+        name,
+        size,
+        samples: times,
+    };
+
+    println!(
+        "{:<26} {:>6} {:>12.2?} {:>12.2?} {:>12.2?} {:>4} samples",
+        name,
+        size,
+        measurement.min(),
+        measurement.median(),
+        measurement.mean(),
+        measurement.samples.len()
+    );
+
+    measurement
+}
+
+/// Follow `parents` back from `end` to the start
+fn hashed_route(parents: &HashMap<Point, Point>, end: Point) -> Vec<Point> {
+    let mut route = vec![end];
+
+    while let Some(parent) = route.last().and_then(|node| parents.get(node)) {
+        route.push(*parent);
+    }
+
+    route.reverse();
+    route
+}
+
+fn hashed_bfs(vertices: &Adjacency, start: Point, end: Point) -> Option<Vec<Point>> {
+    let mut visited = HashSet::from([start]);
+    let mut parents = HashMap::<Point, Point>::new();
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(node) = queue.pop_front() {
+        if node == end {
+            return Some(hashed_route(&parents, end));
+        }
+
+        for next in vertices[&node].keys() {
+            if visited.insert(*next) {
+                parents.insert(*next, node);
+                queue.push_back(*next);
+            }
+        }
+    }
+
+    None
+}
+
+fn hashed_astar(
+    vertices: &Adjacency,
+    start: Point,
+    end: Point,
+    heuristic: &dyn Fn(&Point, &Point) -> usize,
+) -> Option<Vec<Point>> {
+    let mut costs = HashMap::from([(start, 0)]);
+    let mut parents = HashMap::<Point, Point>::new();
+    let mut closed = HashSet::<Point>::new();
+    let mut queue = BinaryHeap::from([Reverse((heuristic(&start, &end), start))]);
+
+    while let Some(Reverse((_, node))) = queue.pop() {
+        if node == end {
+            return Some(hashed_route(&parents, end));
+        }
+
+        if !closed.insert(node) {
+            continue;
+        }
+
+        for (next, weight) in vertices[&node].iter() {
+            let cost = costs[&node] + weight;
+
+            if costs.get(next).is_none_or(|known| cost < *known) {
+                costs.insert(*next, cost);
+                parents.insert(*next, node);
+                queue.push(Reverse((cost + heuristic(next, &end), *next)));
+            }
+        }
+    }
+
+    None
+}
+
+fn run(size: usize, samples: usize) -> Vec<Measurement> {
+    let mut results = vec![
+        bench(
+            "generate/dfs",
+            size,
+            samples,
+            || RandomisedDFS::from_grid_size(size, size).with_seed(SEED),
+            |mut maze| {
+                maze.generate();
+                maze
+            },
+        ),
+        bench(
+            "generate/kruskal",
+            size,
+            samples,
+            || RandomisedKruskal::from_grid_size(size, size).with_seed(SEED),
+            |mut maze| {
+                maze.generate();
+                maze
+            },
+        ),
+        bench(
+            "generate/parallel",
+            size,
+            samples,
+            || ParallelTiled::from_grid_size(size, size).with_seed(SEED),
+            |mut maze| {
+                maze.generate();
+                maze
+            },
+        ),
+    ];
+
+    let mut maze = RandomisedDFS::from_grid_size(size, size).with_seed(SEED);
+    maze.generate();
+
+    let grid = &maze.grid;
+    let builder = GraphBuilder::<Point>::from(grid);
+    let vertices = &builder.vertices;
+    let graph = GraphBuilder::<Point>::from(grid).build();
+    let indexed = IndexedGraph::from(grid);
+    let (start, end) = ((0, 0), (size - 1, size - 1));
+    let end_id = grid.cell_id(end.0, end.1);
+    let heuristic = |a: &Point, b: &Point| grid.manhattan_distance(*a, *b);
+    let indexed_heuristic = |id: usize| grid.manhattan_distance((id % size, id / size), end);
+
+    // Every search has to agree on the length of the route before any of them are timed
+    let length = walk_path(&graph.bfs(start, end).unwrap(), &end)
+        .unwrap()
+        .len();
+    assert_eq!(
+        Some(length),
+        hashed_bfs(vertices, start, end).map(|r| r.len())
+    );
+    assert_eq!(Some(length), indexed.bfs(0, end_id).map(|r| r.len()));
+    assert_eq!(
+        Some(length),
+        hashed_astar(vertices, start, end, &heuristic).map(|r| r.len())
+    );
+
+    results.extend([
+        bench(
+            "graph/build_hashed",
+            size,
+            samples,
+            || (),
+            |_| GraphBuilder::<Point>::from(grid),
+        ),
+        bench(
+            "graph/build",
+            size,
+            samples,
+            || (),
+            |_| GraphBuilder::<Point>::from(grid).build(),
+        ),
+        bench(
+            "graph/build_indexed",
+            size,
+            samples,
+            || (),
+            |_| IndexedGraph::from(grid),
+        ),
+        bench(
+            "solve/bfs_hashed",
+            size,
+            samples,
+            || (),
+            |_| hashed_bfs(vertices, start, end),
+        ),
+        bench("solve/bfs", size, samples, || (), |_| graph.bfs(start, end)),
+        bench(
+            "solve/bfs_indexed",
+            size,
+            samples,
+            || (),
+            |_| indexed.bfs(0, end_id),
+        ),
+        bench(
+            "solve/astar_hashed",
+            size,
+            samples,
+            || (),
+            |_| hashed_astar(vertices, start, end, &heuristic),
+        ),
+        bench(
+            "solve/astar",
+            size,
+            samples,
+            || (),
+            |_| graph.astar(start, end, &heuristic),
+        ),
+        bench(
+            "solve/astar_indexed",
+            size,
+            samples,
+            || (),
+            |_| indexed.astar(0, end_id, &indexed_heuristic),
+        ),
+        bench(
+            "solve/bidirectional_bfs",
+            size,
+            samples,
+            || (),
+            |_| graph.bidirectional_bfs(start, end),
+        ),
+        bench(
+            "solve/bidirectional_astar",
+            size,
+            samples,
+            || (),
+            |_| graph.bidirectional_astar(start, end, &heuristic),
+        ),
+        bench(
+            "solve/dijkstra",
+            size,
+            samples,
+            || (),
+            |_| graph.dijkstra(start, end),
+        ),
+        bench(
+            "render/image",
+            size,
+            samples,
+            || grid.clone(),
+            |grid: Grid| grid.generate_as_image(),
+        ),
+        bench(
+            "render/png",
+            size,
+            samples,
+            || (),
+            |_| grid.write_png(io::sink()),
+        ),
+        bench(
+            "render/svg",
+            size,
+            samples,
+            || (),
+            |_| grid.generate_as_svg(&RenderOptions::default()),
+        ),
+    ]);
+
+    print_speed_ups(&results);
+
+    results
+}
+
+/// How much faster the compressed `Graph` & `IndexedGraph` are than the hashed baseline, going
+/// by the median times
+fn print_speed_ups(results: &[Measurement]) {
+    let median = |name: &str| {
+        results
+            .iter()
+            .find(|m| m.name == name)
+            .map(|m| m.median().as_secs_f64().max(f64::EPSILON))
+    };
+
+    for (name, hashed) in [
+        ("graph/build", "graph/build_hashed"),
+        ("solve/bfs", "solve/bfs_hashed"),
+        ("solve/astar", "solve/astar_hashed"),
+    ] {
+        let (Some(base), Some(csr), Some(indexed)) = (
+            median(hashed),
+            median(name),
+            median(&format!("{}_indexed", name)),
+        ) else {
+            continue;
+        };
+
+        println!(
+            "{:<26} {:>6.1}x csr {:>6.1}x indexed, against hashed",
+            name,
+            base / csr,
+            base / indexed
+        );
+    }
+}
+
+fn write_csv(path: &str, results: &[Measurement]) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    writeln!(
+        file,
+        "benchmark,size,samples,min_ms,median_ms,mean_ms,max_ms"
+    )?;
+
+    for m in results {
+        writeln!(
+            file,
+            "{},{},{},{:.3},{:.3},{:.3},{:.3}",
+            m.name,
+            m.size,
+            m.samples.len(),
+            m.min().as_secs_f64() * 1000.0,
+            m.median().as_secs_f64() * 1000.0,
+            m.mean().as_secs_f64() * 1000.0,
+            m.max().as_secs_f64() * 1000.0
+        )?;
+    }
+
+    file.flush()
+}
+
+fn main() {
+    let options = Options::from_args().unwrap_or_else(|error| {
+        eprintln!("{}", error);
+        std::process::exit(2);
+    });
+    let mut results = Vec::<Measurement>::new();
+
+    println!(
+        "{:<26} {:>6} {:>12} {:>12} {:>12}",
+        "benchmark", "size", "min", "median", "mean"
+    );
+
+    for size in options.sizes.iter() {
+        results.extend(run(*size, options.samples));
+    }
+
+    if let Some(path) = options.csv {
+        write_csv(&path, &results).unwrap();
+        println!("Results written to {}", path);
+    }
+}
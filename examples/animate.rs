@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use maze_v1::algo::{grid::Grid, maze::MazeGenerate, recording::RecordedGrid, RandomisedDFS};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path};
+use maze_v1::render::animation::{generation_frames, solving_frames, write_gif, AnimationOptions};
+
+const MAZE_SIZE: (usize, usize) = (20, 20);
+
+fn main() {
+    let blank = Grid::new(MAZE_SIZE.0, MAZE_SIZE.1);
+    let mut maze_algo = RandomisedDFS::from_grid(RecordedGrid::new(blank.clone()));
+    maze_algo.generate();
+
+    let recorded = &maze_algo.grid;
+    let grid = &recorded.grid;
+    let start = (0, 0);
+    let end = (grid.cols - 1, grid.rows - 1);
+
+    let mut options = AnimationOptions {
+        stride: 4,
+        ..AnimationOptions::default()
+    };
+
+    let frames = generation_frames(&blank, &recorded.carves, &options);
+    let file = BufWriter::new(File::create("maze_generation.gif").unwrap());
+    write_gif(frames, file).unwrap();
+
+    println!("Generation saved");
+
+    let graph = GraphBuilder::<(usize, usize)>::from(grid).build();
+    graph.record_trace();
+
+    let solution = graph
+        .bfs(start, end)
+        .and_then(|path| walk_path(&path, &end));
+
+    options.render.entrance = Some(start);
+    options.render.exit = Some(end);
+    options.stride = 2;
+
+    let frames = solving_frames(grid, &graph.get_trace(), solution, &options);
+    let file = BufWriter::new(File::create("maze_solving.gif").unwrap());
+    write_gif(frames, file).unwrap();
+
+    println!("Solving saved");
+}
@@ -0,0 +1,46 @@
+use image::Rgba;
+use maze_v1::algo::{maze::MazeGenerate, RandomisedDFS};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path};
+use maze_v1::render::{
+    colour::{ColourOptions, Gradient},
+    RenderOptions,
+};
+
+const MAZE_SIZE: (usize, usize) = (60, 60);
+
+fn main() {
+    let mut maze_algo = RandomisedDFS::from_grid_size(MAZE_SIZE.0, MAZE_SIZE.1);
+    maze_algo.generate();
+
+    let grid = &maze_algo.grid;
+    // Entrance & exit at the ends of the longest path through the maze
+    let (start, end) = grid.furthest_cells().unwrap();
+
+    let graph = GraphBuilder::<(usize, usize)>::from(grid).build();
+    let solution = graph
+        .bfs(start, end)
+        .and_then(|path| walk_path(&path, &end));
+
+    let options = RenderOptions {
+        cell_size: 6,
+        wall_width: 2,
+        entrance: Some(start),
+        exit: Some(end),
+        solution,
+    };
+
+    let image = grid.generate_as_colour_image(
+        &options,
+        &ColourOptions {
+            distances_from: Some(start),
+            gradient: Gradient::heat(),
+            solution: Rgba([40, 120, 255, 255]),
+            dead_end: Some(Rgba([90, 90, 90, 255])),
+            ..ColourOptions::default()
+        },
+    );
+
+    image.save("maze_colour.png").unwrap();
+
+    println!("Maze saved");
+}
@@ -0,0 +1,37 @@
+use maze_v1::algo::{
+    grid3d::{Grid3D, Point3D},
+    maze::MazeGenerate,
+    RandomisedDFS,
+};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path};
+
+const MAZE_SIZE: (usize, usize, usize) = (20, 20, 3);
+
+fn main() {
+    let mut maze_algo =
+        RandomisedDFS::from_grid(Grid3D::new(MAZE_SIZE.0, MAZE_SIZE.1, MAZE_SIZE.2));
+    maze_algo.generate();
+
+    println!("Maze generated");
+
+    let start: Point3D = (0, 0, 0);
+    let end: Point3D = (MAZE_SIZE.1 - 1, MAZE_SIZE.0 - 1, MAZE_SIZE.2 - 1);
+
+    let graph = GraphBuilder::<Point3D>::from(&maze_algo.grid).build();
+    let grid = &maze_algo.grid;
+
+    graph.astar(start, end, &|p1, p2| grid.manhattan_distance(*p1, *p2));
+
+    if let Some(path) = walk_path(&graph.get_path(), &end) {
+        println!("Path length: {}", path.len());
+        println!("{}", grid.solution_listing(&path));
+    }
+
+    maze_algo
+        .grid
+        .generate_as_image()
+        .save("maze_levels.png")
+        .unwrap();
+
+    println!("Maze saved");
+}
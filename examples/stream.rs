@@ -0,0 +1,24 @@
+use std::time::Instant;
+
+use maze_v1::algo::{maze::MazeGenerate, ParallelTiled};
+
+/// Width & height of the maze in cells, can be changed with the first argument
+const MAZE_SIZE: usize = 5000;
+
+fn main() {
+    let size = std::env::args()
+        .nth(1)
+        .and_then(|arg| arg.parse::<usize>().ok())
+        .unwrap_or(MAZE_SIZE);
+
+    let now = Instant::now();
+    let mut maze_algo = ParallelTiled::from_grid_size(size, size);
+    maze_algo.generate();
+
+    println!("Maze generated in {:.2?}", now.elapsed());
+
+    let now = Instant::now();
+    maze_algo.grid.save_png("maze_stream.png").unwrap();
+
+    println!("Maze saved in {:.2?}", now.elapsed());
+}
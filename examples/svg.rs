@@ -0,0 +1,30 @@
+use maze_v1::algo::{maze::MazeGenerate, RandomisedDFS};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path};
+use maze_v1::render::RenderOptions;
+
+const MAZE_SIZE: (usize, usize) = (40, 40);
+
+fn main() {
+    let mut maze_algo = RandomisedDFS::from_grid_size(MAZE_SIZE.0, MAZE_SIZE.1);
+    maze_algo.generate();
+
+    let grid = &maze_algo.grid;
+    let start = (0, 0);
+    let end = (grid.cols - 1, grid.rows - 1);
+
+    let graph = GraphBuilder::<(usize, usize)>::from(grid).build();
+    let solution = graph
+        .bfs(start, end)
+        .and_then(|path| walk_path(&path, &end));
+
+    let svg = grid.generate_as_svg(&RenderOptions {
+        entrance: Some(start),
+        exit: Some(end),
+        solution,
+        ..RenderOptions::default()
+    });
+
+    std::fs::write("maze.svg", svg).unwrap();
+
+    println!("Maze saved");
+}
@@ -0,0 +1,35 @@
+use maze_v1::algo::{maze::MazeGenerate, weave::WeaveGrid, RandomisedDFS, RandomisedKruskal};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path};
+
+const MAZE_SIZE: (usize, usize) = (30, 30);
+const DENSITY: f64 = 0.5;
+
+fn main() {
+    let mut dfs = RandomisedDFS::from_grid(WeaveGrid::new(MAZE_SIZE.0, MAZE_SIZE.1, DENSITY));
+    dfs.generate();
+
+    let mut kruskal =
+        RandomisedKruskal::from_grid(WeaveGrid::new(MAZE_SIZE.0, MAZE_SIZE.1, DENSITY));
+    kruskal.generate();
+
+    for (name, weave) in [("dfs", dfs.grid), ("kruskal", kruskal.grid)] {
+        let crossings = weave.grid.cells().filter(|c| c.under).count();
+        println!("{} - crossings: {}", name, crossings);
+
+        let end = (MAZE_SIZE.1 - 1, MAZE_SIZE.0 - 1);
+        let graph = GraphBuilder::<(usize, usize)>::from(&weave).build();
+
+        if let Some(path) = graph.bfs((0, 0), end) {
+            println!(
+                "{} - path length: {}",
+                name,
+                walk_path(&path, &end).unwrap().len()
+            );
+        }
+
+        weave
+            .generate_as_image()
+            .save(format!("maze_weave_{}.png", name))
+            .unwrap();
+    }
+}
@@ -1,5 +1,14 @@
+pub mod agents;
 pub mod grid;
+pub mod grid3d;
+pub mod kruskal;
 pub mod maze;
+pub mod observer;
+pub mod parallel;
 pub mod randomised_dfs;
+pub mod recording;
+pub mod weave;
 
+pub use kruskal::*;
+pub use parallel::*;
 pub use randomised_dfs::*;
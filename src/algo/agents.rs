@@ -0,0 +1,407 @@
+use std::collections::{HashMap, VecDeque};
+
+use super::grid::Grid;
+use super::maze::Direction;
+use crate::util::BitSet;
+
+/// The hand an agent keeps on the wall
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum Hand {
+    #[default]
+    Left,
+    Right,
+}
+
+/// What an agent did on its way through the maze
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AgentRun {
+    /// Route from the start to the end with any loops the agent walked removed, `None` if the
+    /// agent gave up
+    pub path: Option<Vec<(usize, usize)>>,
+    /// Every cell the agent stepped on in order, including going back over old ground
+    pub trace: Vec<(usize, usize)>,
+}
+
+impl AgentRun {
+    fn finished(trace: Vec<(usize, usize)>) -> Self {
+        Self {
+            path: Some(erase_loops(&trace)),
+            trace,
+        }
+    }
+
+    fn gave_up(trace: Vec<(usize, usize)>) -> Self {
+        Self { path: None, trace }
+    }
+}
+
+/// Cut out every loop in a walk, leaving the direct route between its ends
+fn erase_loops(trace: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut path = Vec::<(usize, usize)>::new();
+    let mut index = HashMap::<(usize, usize), usize>::new();
+
+    for cell in trace {
+        match index.get(cell) {
+            Some(&idx) => {
+                for removed in path.drain(idx + 1..) {
+                    index.remove(&removed);
+                }
+            }
+            None => {
+                index.insert(*cell, path.len());
+                path.push(*cell);
+            }
+        }
+    }
+
+    path
+}
+
+/// The cell reached by walking from `pos` in `dir`, `None` if there's a wall or a one way door
+/// facing the other way in the way
+fn step(grid: &Grid, pos: (usize, usize), dir: Direction) -> Option<(usize, usize)> {
+    if !grid.can_pass(pos.0, pos.1, dir) {
+        return None;
+    }
+
+    grid.get_neighbor_cell(&grid.cell(pos.0, pos.1), dir)
+        .map(|n| n.to_visited_id())
+}
+
+/// Turn `dir` a number of quarter turns clockwise, negative turns go anticlockwise
+fn turn(dir: Direction, quarters: i32) -> Direction {
+    Direction::from_index(dir.index() + quarters.rem_euclid(4) as usize)
+}
+
+/// Keep one hand on the wall and walk until reaching `end`. Only works when `end` is on a wall
+/// connected to the one the agent starts on, so on a braided maze it can walk in circles forever.
+/// The agent gives up when it's back in a cell facing the same way as before.
+pub fn wall_follower(
+    grid: &Grid,
+    start: (usize, usize),
+    end: (usize, usize),
+    hand: Hand,
+) -> AgentRun {
+    // Order to try each way out in, relative to the way the agent is facing
+    let turns = match hand {
+        Hand::Left => [-1, 0, 1, 2],
+        Hand::Right => [1, 0, -1, 2],
+    };
+
+    let mut trace = vec![start];
+    // Each cell & way the agent could be facing in it
+    let mut seen = BitSet::new(grid.rows * grid.cols * 4);
+    let mut pos = start;
+    let mut facing = grid
+        .open_directions(start.0, start.1)
+        .first()
+        .copied()
+        .unwrap_or(Direction::Top);
+
+    while pos != end {
+        if !seen.insert(grid.cell_id(pos.0, pos.1) * 4 + facing.index()) {
+            return AgentRun::gave_up(trace);
+        }
+
+        let next = turns.iter().find_map(|quarters| {
+            let dir = turn(facing, *quarters);
+            step(grid, pos, dir).map(|next| (dir, next))
+        });
+
+        // Walled in on every side
+        let Some((dir, next)) = next else {
+            return AgentRun::gave_up(trace);
+        };
+
+        facing = dir;
+        pos = next;
+        trace.push(pos);
+    }
+
+    AgentRun::finished(trace)
+}
+
+/// Walk in `heading` until blocked, then follow the wall with `hand` while counting the turns
+/// made. The wall is only let go once the turns add back up to 0, which stops the agent getting
+/// stuck circling a pillar. It's built to escape to the edge of a maze rather than to find a cell
+/// in the middle of one, so the agent gives up if it repeats itself before finding `end`.
+pub fn pledge(
+    grid: &Grid,
+    start: (usize, usize),
+    end: (usize, usize),
+    heading: Direction,
+    hand: Hand,
+) -> AgentRun {
+    // Going straight on where possible, otherwise turning away from the hand on the wall
+    let free = match hand {
+        Hand::Left => [0, 1, 2, 3],
+        Hand::Right => [0, -1, -2, -3],
+    };
+    let following = match hand {
+        Hand::Left => [-1, 0, 1, 2],
+        Hand::Right => [1, 0, -1, 2],
+    };
+    // The count can keep growing on some mazes, so the walk is capped as well
+    let limit = grid.rows * grid.cols * 16;
+
+    let mut trace = vec![start];
+    // Turn counts the agent has been in each cell with, there's rarely more than one or two
+    let mut seen = vec![Vec::<i32>::new(); grid.rows * grid.cols];
+    let mut pos = start;
+    let mut count = 0;
+
+    while pos != end {
+        let counts = &mut seen[grid.cell_id(pos.0, pos.1)];
+
+        if counts.contains(&count) || trace.len() > limit {
+            return AgentRun::gave_up(trace);
+        }
+
+        counts.push(count);
+
+        let facing = turn(heading, count);
+        let turns = match count {
+            0 => free,
+            _ => following,
+        };
+
+        let next = turns.iter().find_map(|quarters| {
+            step(grid, pos, turn(facing, *quarters)).map(|next| (*quarters, next))
+        });
+
+        let Some((quarters, next)) = next else {
+            return AgentRun::gave_up(trace);
+        };
+
+        // Turning around always goes the way of the free hand
+        count += match (quarters, hand) {
+            (2, Hand::Right) => -2,
+            _ => quarters,
+        };
+        pos = next;
+        trace.push(pos);
+    }
+
+    AgentRun::finished(trace)
+}
+
+/// Trémaux's algorithm, marking each passage every time it's walked. Based off
+/// [this description](https://en.wikipedia.org/wiki/Maze-solving_algorithm#Tr%C3%A9maux's_algorithm)
+///
+/// - Dead ends are walked back out of
+/// - Arriving at a junction that's been visited before along a new passage, turn back
+/// - Otherwise take the passage with the fewest marks, never one with two marks
+///
+/// Works on any maze, including ones with loops, and gives up once every passage is marked twice.
+pub fn tremaux(grid: &Grid, start: (usize, usize), end: (usize, usize)) -> AgentRun {
+    // Marks on each side of every passage by cell id & direction, both sides are kept the same
+    let mut marks = vec![0u8; grid.rows * grid.cols * 4];
+
+    let mut trace = vec![start];
+    let mut pos = start;
+    // The way back to the cell the agent just came from
+    let mut back: Option<Direction> = None;
+
+    while pos != end {
+        let id = grid.cell_id(pos.0, pos.1);
+        let mark = |dir: Direction| marks[id * 4 + dir.index()];
+        let others = grid
+            .open_directions(pos.0, pos.1)
+            .into_iter()
+            .filter(|dir| Some(*dir) != back && step(grid, pos, *dir).is_some())
+            .collect::<Vec<_>>();
+
+        let next = match back {
+            Some(back) if others.is_empty() => Some(back),
+            Some(back) if mark(back) == 1 && others.iter().any(|dir| mark(*dir) > 0) => Some(back),
+            // The way back is only taken when everything else has more marks
+            _ => others
+                .iter()
+                .copied()
+                .chain(back)
+                .min_by_key(|dir| mark(*dir)),
+        };
+
+        let Some(dir) = next.filter(|dir| mark(*dir) < 2) else {
+            return AgentRun::gave_up(trace);
+        };
+
+        let next = step(grid, pos, dir).unwrap();
+        marks[id * 4 + dir.index()] += 1;
+        marks[grid.cell_id(next.0, next.1) * 4 + dir.opposite().index()] += 1;
+        back = Some(dir.opposite());
+        pos = next;
+        trace.push(pos);
+    }
+
+    AgentRun::finished(trace)
+}
+
+/// Fill in every dead end apart from `start` & `end`, then any cell left as a dead end by the
+/// filling, until only the passages between them are left. The trace is the cells in the order
+/// they were filled. On a maze with loops the loops are left behind, the shortest route through
+/// what's left is returned.
+pub fn dead_end_filling(grid: &Grid, start: (usize, usize), end: (usize, usize)) -> AgentRun {
+    let mut filled = BitSet::new(grid.rows * grid.cols);
+    let mut trace = Vec::<(usize, usize)>::new();
+    let mut queue = VecDeque::from(grid.dead_ends());
+
+    let exits = |filled: &BitSet, pos: (usize, usize)| {
+        grid.open_directions(pos.0, pos.1)
+            .into_iter()
+            .filter_map(|dir| step(grid, pos, dir))
+            .filter(|cell| !filled.contains(grid.cell_id(cell.0, cell.1)))
+            .collect::<Vec<_>>()
+    };
+
+    while let Some(cell) = queue.pop_front() {
+        let id = grid.cell_id(cell.0, cell.1);
+
+        if cell == start || cell == end || filled.contains(id) {
+            continue;
+        }
+
+        let open = exits(&filled, cell);
+
+        if open.len() > 1 {
+            continue;
+        }
+
+        filled.insert(id);
+        trace.push(cell);
+        queue.extend(open);
+    }
+
+    // Shortest route through the cells left open
+    let mut parents = vec![None::<(usize, usize)>; grid.rows * grid.cols];
+    let mut seen = BitSet::new(grid.rows * grid.cols);
+    let mut queue = VecDeque::from([start]);
+    seen.insert(grid.cell_id(start.0, start.1));
+
+    while let Some(cell) = queue.pop_front() {
+        if cell == end {
+            let mut path = vec![end];
+
+            while let Some(parent) = path.last().and_then(|c| parents[grid.cell_id(c.0, c.1)]) {
+                path.push(parent);
+            }
+
+            path.reverse();
+
+            return AgentRun {
+                path: Some(path),
+                trace,
+            };
+        }
+
+        for next in exits(&filled, cell) {
+            let id = grid.cell_id(next.0, next.1);
+
+            if seen.insert(id) {
+                parents[id] = Some(cell);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    AgentRun::gave_up(trace)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+    use crate::graph::{builder::GraphBuilder, graph::walk_path};
+
+    /// A loop round the outside with the end on an island in the middle
+    const ISLAND: &str = "\
++---+---+---+
+|           |
++   +---+   +
+|   |   |   |
++   +   +   +
+|           |
++---+---+---+
+";
+
+    fn assert_walkable(grid: &Grid, path: &[(usize, usize)]) {
+        for pair in path.windows(2) {
+            let dir = Direction::ALL
+                .into_iter()
+                .find(|dir| step(grid, pair[0], *dir) == Some(pair[1]));
+
+            assert!(dir.is_some(), "{:?} isn't open to {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn test_agents_agree_on_perfect_maze() {
+        let mut maze = RandomisedDFS::from_grid_size(12, 12);
+        maze.generate();
+
+        let grid = &maze.grid;
+        let (start, end) = ((0, 0), (11, 11));
+        let graph = GraphBuilder::<(usize, usize)>::from(grid).build();
+        let expected = walk_path(&graph.bfs(start, end).unwrap(), &end);
+
+        // A perfect maze only has one route, however it's found
+        for run in [
+            wall_follower(grid, start, end, Hand::Left),
+            wall_follower(grid, start, end, Hand::Right),
+            tremaux(grid, start, end),
+        ] {
+            assert_eq!(expected, run.path);
+            assert_walkable(grid, &run.trace);
+        }
+
+        let run = dead_end_filling(grid, start, end);
+        assert_eq!(expected, run.path);
+        // Everything off the route gets filled
+        assert_eq!(144 - expected.unwrap().len(), run.trace.len());
+    }
+
+    #[test]
+    fn test_wall_followers_circle_island() {
+        let grid: Grid = ISLAND.parse().unwrap();
+        let (start, end) = ((0, 0), (1, 1));
+
+        for hand in [Hand::Left, Hand::Right] {
+            let run = wall_follower(&grid, start, end, hand);
+
+            assert_eq!(None, run.path);
+            // Once round the loop & a step into the second lap
+            assert_eq!(10, run.trace.len());
+        }
+
+        let run = tremaux(&grid, start, end);
+        let path = run.path.unwrap();
+        assert_eq!((start, end), (path[0], *path.last().unwrap()));
+        assert_walkable(&grid, &path);
+
+        let run = dead_end_filling(&grid, start, end);
+        assert_eq!(Some(vec![(0, 0), (0, 1), (0, 2), (1, 2), (1, 1)]), run.path);
+    }
+
+    #[test]
+    fn test_pledge_escapes_pillar() {
+        // Heading right from (0, 1) hits the pillar in the middle, a plain wall follower would
+        // go round it forever but Pledge lets go once it's facing right again
+        let grid: Grid = "\
++---+---+---+---+
+|               |
++   +---+   +   +
+|   |   |       |
++   +---+   +   +
+|               |
++---+---+---+---+
+"
+        .parse()
+        .unwrap();
+
+        let run = pledge(&grid, (0, 1), (3, 1), Direction::Right, Hand::Left);
+
+        assert_eq!(Some(&(3, 1)), run.trace.last());
+        assert_walkable(&grid, &run.trace);
+        assert!(run.path.is_some());
+    }
+}
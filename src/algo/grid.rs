@@ -1,169 +1,825 @@
-use super::maze::{Cell, Direction};
+use super::maze::{Cell, Direction, MazeGrid};
+use crate::graph::{builder::GraphBuilder, indexed::IndexedGraph};
 use image::{ImageBuffer, Luma};
-use std::convert::Into;
+use rand::{seq::SliceRandom, Rng};
+use std::collections::HashMap;
+use std::convert::From;
+use std::fmt;
 
-#[derive(Debug, Clone)]
-pub struct Grid {
-    pub cols: usize,
-    pub rows: usize,
-    pub matrix: Vec<Vec<Cell>>,
-}
-
-impl Grid {
-    fn build_matrix(rows: usize, cols: usize) -> Vec<Vec<Cell>> {
-        let mut matrix = Vec::<Vec<Cell>>::with_capacity(rows);
-        for y in 0..rows {
-            let mut row = Vec::<Cell>::with_capacity(cols);
+/// Colour used for openings in the border where a passage crosses a wrapped edge
+pub const SEAM_COLOUR: [u8; 1] = [200u8];
 
-            for x in 0..cols {
-                row.push(Cell::new(x, y));
-            }
+/// How the edges of a `Grid` connect to each other.
+///
+/// Wrapping happens on the left & right edges first, so a `Cylinder` and `Mobius`
+/// only ever wrap horizontally while a `Torus` wraps on both axes.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub enum Topology {
+    /// No edges wrap, the outside of the grid is a solid wall
+    #[default]
+    Plane,
+    /// Left & right edges wrap
+    Cylinder,
+    /// Left & right edges wrap, top & bottom edges wrap
+    Torus,
+    /// Left & right edges wrap, flipping the row upside down as it crosses
+    Mobius,
+}
 
-            matrix.push(row);
-        }
+impl Topology {
+    pub fn wraps_horizontally(&self) -> bool {
+        !matches!(self, Topology::Plane)
+    }
 
-        matrix
+    pub fn wraps_vertically(&self) -> bool {
+        matches!(self, Topology::Torus)
     }
 
-    pub fn get_neighbor_cell(&self, starting_cell: &Cell, dir: Direction) -> Option<Cell> {
-        // (row, col)
-        let index_tuple: (usize, usize) = match dir {
+    /// Take a single step from `(x, y)` on a board of `size` (width, height).
+    ///
+    /// Returns `None` when the step walks off a non-wrapping edge. An axis of length 1 never wraps
+    /// as the cell would be its own neighbour.
+    pub fn step(
+        &self,
+        x: usize,
+        y: usize,
+        dir: Direction,
+        size: (usize, usize),
+    ) -> Option<(usize, usize)> {
+        let (width, height) = size;
+        let wrap_x = self.wraps_horizontally() && width > 1;
+        let wrap_y = self.wraps_vertically() && height > 1;
+
+        match dir {
             Direction::Top => {
-                if starting_cell.y == 0 {
-                    return None;
+                if y > 0 {
+                    Some((x, y - 1))
+                } else if wrap_y {
+                    Some((x, height - 1))
+                } else {
+                    None
                 }
-
-                (starting_cell.y - 1, starting_cell.x)
             }
             Direction::Right => {
-                if starting_cell.x == self.cols - 1 {
-                    return None;
+                if x + 1 < width {
+                    Some((x + 1, y))
+                } else if wrap_x {
+                    Some((0, self.wrapped_row(y, height)))
+                } else {
+                    None
                 }
-
-                (starting_cell.y, starting_cell.x + 1)
             }
             Direction::Bottom => {
-                if starting_cell.y == self.rows - 1 {
-                    return None;
+                if y + 1 < height {
+                    Some((x, y + 1))
+                } else if wrap_y {
+                    Some((x, 0))
+                } else {
+                    None
                 }
-
-                (starting_cell.y + 1, starting_cell.x)
             }
             Direction::Left => {
-                if starting_cell.x == 0 {
-                    return None;
+                if x > 0 {
+                    Some((x - 1, y))
+                } else if wrap_x {
+                    Some((width - 1, self.wrapped_row(y, height)))
+                } else {
+                    None
                 }
+            }
+        }
+    }
+
+    /// The row a passage lands on after crossing the left/right seam
+    pub fn wrapped_row(&self, y: usize, height: usize) -> usize {
+        match self {
+            Topology::Mobius => height - 1 - y,
+            _ => y,
+        }
+    }
+
+    /// Manhattan distance which is allowed to cross any wrapping edges, used as an A* heuristic.
+    /// Never overestimates so the heuristic stays admissible.
+    pub fn manhattan_distance(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        size: (usize, usize),
+    ) -> usize {
+        let (width, height) = size;
+        let dx = from.0.abs_diff(to.0);
+        let dy = from.1.abs_diff(to.1);
+
+        let dy = if self.wraps_vertically() {
+            dy.min(height - dy)
+        } else {
+            dy
+        };
+
+        let direct = dx + dy;
+
+        if !self.wraps_horizontally() {
+            return direct;
+        }
+
+        // Crossing the seam once, which for a Möbius strip also lands on the flipped row
+        let seam_dx = width - dx;
+        let seam_dy = match self {
+            Topology::Mobius => from.1.abs_diff(self.wrapped_row(to.1, height)),
+            _ => dy,
+        };
+
+        direct.min(seam_dx + seam_dy)
+    }
+}
+
+/// Something wrong with the layout of a `Grid`, found by `Grid::validate`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum GridError {
+    /// The walls stored don't cover `rows` by `cols` cells
+    BadDimensions {
+        rows: usize,
+        cols: usize,
+        cells: usize,
+    },
+    /// A cell with a tunnel under it isn't a straight corridor running across the tunnel, with a
+    /// cell on each side for the tunnel to come out in
+    BadCrossing((usize, usize)),
+    /// A one way door is stored for the wall on `dir` of `cell`, but that wall is standing or on
+    /// the outside of the grid
+    BadDoor {
+        cell: (usize, usize),
+        dir: Direction,
+    },
+}
 
-                (starting_cell.y, starting_cell.x - 1)
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::BadDimensions { rows, cols, cells } => write!(
+                f,
+                "grid should be {} rows of {} cells, but has walls for {} cells",
+                rows, cols, cells
+            ),
+            GridError::BadCrossing(cell) => {
+                write!(
+                    f,
+                    "cell {:?} has a tunnel under it but isn't a crossing",
+                    cell
+                )
             }
+            GridError::BadDoor { cell, dir } => write!(
+                f,
+                "one way door on {:?} of {:?} isn't an open wall",
+                dir, cell
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+/// Bit of a cell's pair in `Grid::walls` for the wall on its right
+const RIGHT_WALL: u8 = 0b01;
+/// Bit of a cell's pair in `Grid::walls` for the wall below it
+const BOTTOM_WALL: u8 = 0b10;
+
+/// Whether heading in `dir` goes through a wall from the side of the cell it's stored with, see
+/// `Grid::one_way`
+fn is_forward(dir: Direction) -> bool {
+    matches!(dir, Direction::Right | Direction::Bottom)
+}
+
+/// A rectangular maze stored as two bits per cell in row-major order, one for the wall on the
+/// right of the cell & one for the wall below it. The top & left walls of a cell are the bottom &
+/// right walls of its neighbours, so each wall is only stored once. `Cell`s are built on demand.
+/// An open wall can also be a one way door, see `set_one_way`.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    pub cols: usize,
+    pub rows: usize,
+    pub topology: Topology,
+    /// Four cells to a byte starting from the low bits, a set bit is a standing wall
+    walls: Vec<u8>,
+    /// Ids of cells with a weave tunnel going under them & the way it runs, see `WeaveGrid`
+    under: HashMap<usize, Direction>,
+    /// One way doors keyed by the slot of their wall in `walls`, `true` when the door can only be
+    /// passed heading right or down from the cell the wall is stored with, `false` for left or up
+    one_way: HashMap<(usize, u8), bool>,
+}
+
+impl Grid {
+    pub fn get_neighbor_cell(&self, starting_cell: &Cell, dir: Direction) -> Option<Cell> {
+        let (x, y) = self.topology.step(
+            starting_cell.x,
+            starting_cell.y,
+            dir,
+            (self.cols, self.rows),
+        )?;
+
+        Some(self.cell(x, y))
+    }
+
+    /// The cell at `(x, y)` with its walls as they are now, changing it doesn't change the grid
+    pub fn cell(&self, x: usize, y: usize) -> Cell {
+        Cell {
+            x,
+            y,
+            walls: Direction::ALL.map(|dir| self.has_wall(x, y, dir)),
+            under: self.is_under(x, y),
+        }
+    }
+
+    /// Every cell in row-major order
+    pub fn cells(&self) -> impl Iterator<Item = Cell> + '_ {
+        (0..self.rows).flat_map(move |y| (0..self.cols).map(move |x| self.cell(x, y)))
+    }
+
+    /// The cell id & bit the wall on `dir` of `(x, y)` is stored in, `None` on an edge of the
+    /// grid that doesn't wrap
+    fn wall_slot(&self, x: usize, y: usize, dir: Direction) -> Option<(usize, u8)> {
+        let (nx, ny) = self.topology.step(x, y, dir, (self.cols, self.rows))?;
+
+        Some(match dir {
+            Direction::Top => (self.cell_id(nx, ny), BOTTOM_WALL),
+            Direction::Right => (self.cell_id(x, y), RIGHT_WALL),
+            Direction::Bottom => (self.cell_id(x, y), BOTTOM_WALL),
+            Direction::Left => (self.cell_id(nx, ny), RIGHT_WALL),
+        })
+    }
+
+    /// Whether the stored wall on `dir` of the cell at `(x, y)` is standing, ignoring tunnels
+    fn stored_wall(&self, x: usize, y: usize, dir: Direction) -> bool {
+        self.wall_slot(x, y, dir)
+            .is_none_or(|(id, bit)| self.walls[id / 4] >> (id % 4 * 2) & bit != 0)
+    }
+
+    /// Whether the wall on `dir` of the cell at `(x, y)` is standing, the outside of a grid that
+    /// doesn't wrap is always a wall. A wall into a crossing, a cell with a tunnel under it, is
+    /// open when looked at from the tunnel's side as that's where the tunnel comes through.
+    pub fn has_wall(&self, x: usize, y: usize, dir: Direction) -> bool {
+        if !self.stored_wall(x, y, dir) {
+            return false;
+        }
+
+        if self.under.is_empty() {
+            return true;
+        }
+
+        self.topology
+            .step(x, y, dir, (self.cols, self.rows))
+            .and_then(|(nx, ny)| self.tunnel_under(nx, ny))
+            .is_none_or(|tunnel| tunnel != dir && tunnel != dir.opposite())
+    }
+
+    /// Put up or knock down the wall on `dir` of the cell at `(x, y)`, which is also the wall of
+    /// the neighbour on that side. Walls on the outside of a grid that doesn't wrap can't be
+    /// changed.
+    pub fn set_wall(&mut self, x: usize, y: usize, dir: Direction, standing: bool) {
+        let Some((id, bit)) = self.wall_slot(x, y, dir) else {
+            return;
+        };
+
+        let mask = bit << (id % 4 * 2);
+        self.one_way.remove(&(id, bit));
+
+        match standing {
+            true => self.walls[id / 4] |= mask,
+            false => self.walls[id / 4] &= !mask,
+        }
+    }
+
+    /// Turn the wall on `dir` of the cell at `(x, y)` into a one way door, opening it if it's
+    /// standing. It can only be passed leaving the cell through `dir`, not coming back the other
+    /// way. `set_wall` turns it back into an ordinary wall.
+    pub fn set_one_way(&mut self, x: usize, y: usize, dir: Direction) {
+        let Some(slot) = self.wall_slot(x, y, dir) else {
+            return;
+        };
+
+        self.set_wall(x, y, dir, false);
+        self.one_way.insert(slot, is_forward(dir));
+    }
+
+    /// Whether the wall on `dir` of the cell at `(x, y)` is a one way door, whichever way it goes
+    pub fn is_one_way(&self, x: usize, y: usize, dir: Direction) -> bool {
+        self.wall_slot(x, y, dir)
+            .is_some_and(|slot| self.one_way.contains_key(&slot))
+    }
+
+    /// Whether the cell at `(x, y)` can be left through the wall on `dir`, it has to be open and
+    /// not a one way door pointing the other way
+    pub fn can_pass(&self, x: usize, y: usize, dir: Direction) -> bool {
+        if self.has_wall(x, y, dir) {
+            return false;
+        }
+
+        match self.one_way.is_empty() {
+            true => true,
+            false => self
+                .wall_slot(x, y, dir)
+                .and_then(|slot| self.one_way.get(&slot))
+                .is_none_or(|forward| *forward == is_forward(dir)),
+        }
+    }
+
+    /// Every one way door as the cell it leads out of & the direction it goes, in order of cell
+    pub fn one_way_doors(&self) -> Vec<((usize, usize), Direction)> {
+        let mut doors = self
+            .one_way
+            .iter()
+            .map(|((id, bit), forward)| {
+                let (x, y) = (id % self.cols, id / self.cols);
+                let dir = match *bit {
+                    RIGHT_WALL => Direction::Right,
+                    _ => Direction::Bottom,
+                };
+
+                match forward {
+                    true => ((x, y), dir),
+                    // Stepping through the stored wall and turning round
+                    false => {
+                        let (nx, ny) = self
+                            .topology
+                            .step(x, y, dir, (self.cols, self.rows))
+                            .unwrap_or((x, y));
+
+                        ((nx, ny), dir.opposite())
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        doors.sort_by_key(|((x, y), dir)| (*y, *x, dir.index()));
+        doors
+    }
+
+    /// Whether a weave tunnel goes under the cell at `(x, y)`
+    pub fn is_under(&self, x: usize, y: usize) -> bool {
+        self.tunnel_under(x, y).is_some()
+    }
+
+    /// The way a tunnel under the cell at `(x, y)` runs, it can be walked in either direction
+    pub fn tunnel_under(&self, x: usize, y: usize) -> Option<Direction> {
+        match self.under.is_empty() {
+            true => None,
+            false => self.under.get(&self.cell_id(x, y)).copied(),
+        }
+    }
+
+    /// Dig a tunnel running in `tunnel` under the cell at `(x, y)`, or fill it in with `None`. The
+    /// tunnel goes through the walls of the cell on that side, which should be left standing.
+    pub fn set_under(&mut self, x: usize, y: usize, tunnel: Option<Direction>) {
+        let id = self.cell_id(x, y);
+
+        match tunnel {
+            Some(dir) => self.under.insert(id, dir),
+            None => self.under.remove(&id),
         };
+    }
+
+    /// The cell reached leaving `(x, y)` through the open wall on `dir`. A passage into the side
+    /// of a crossing goes under it and comes out at the cell past it. `None` if the wall is
+    /// standing.
+    pub fn passage_to(&self, x: usize, y: usize, dir: Direction) -> Option<(usize, usize)> {
+        if self.has_wall(x, y, dir) {
+            return None;
+        }
+
+        let bounds = (self.cols, self.rows);
+        let (nx, ny) = self.topology.step(x, y, dir, bounds)?;
+
+        match self.tunnel_under(nx, ny) {
+            Some(tunnel) if tunnel == dir || tunnel == dir.opposite() => {
+                self.topology.step(nx, ny, dir, bounds)
+            }
+            _ => Some((nx, ny)),
+        }
+    }
+
+    /// Whether the wall on `dir` of the cell lies on a wrapped edge of the grid
+    pub fn is_seam(&self, cell: &Cell, dir: Direction) -> bool {
+        let (horizontal, vertical) = (
+            self.topology.wraps_horizontally(),
+            self.topology.wraps_vertically(),
+        );
+
+        match dir {
+            Direction::Top => vertical && cell.y == 0,
+            Direction::Right => horizontal && cell.x == self.cols - 1,
+            Direction::Bottom => vertical && cell.y == self.rows - 1,
+            Direction::Left => horizontal && cell.x == 0,
+        }
+    }
 
-        Some(self.matrix[index_tuple.0][index_tuple.1])
+    /// Manhattan distance between two cells that respects the grid's topology
+    pub fn manhattan_distance(&self, from: (usize, usize), to: (usize, usize)) -> usize {
+        self.topology
+            .manhattan_distance(from, to, (self.cols, self.rows))
     }
 
     pub fn new(rows: usize, cols: usize) -> Self {
-        let matrix = Grid::build_matrix(rows, cols);
+        Grid::with_topology(rows, cols, Topology::Plane)
+    }
 
-        Self { rows, cols, matrix }
+    pub fn with_topology(rows: usize, cols: usize, topology: Topology) -> Self {
+        Self {
+            rows,
+            cols,
+            topology,
+            walls: vec![u8::MAX; (rows * cols).div_ceil(4)],
+            under: HashMap::new(),
+            one_way: HashMap::new(),
+        }
     }
 
     pub fn generate_as_image(self) -> ImageBuffer<Luma<u8>, Vec<u8>> {
         let image: ImageBuffer<Luma<u8>, Vec<u8>> = self.into();
         image
     }
+
+    /// Directions out of the cell at `(x, y)` without a wall
+    pub fn open_directions(&self, x: usize, y: usize) -> Vec<Direction> {
+        Direction::ALL
+            .into_iter()
+            .filter(|dir| !self.has_wall(x, y, *dir))
+            .collect()
+    }
+
+    /// Cells with a single way in or out
+    pub fn dead_ends(&self) -> Vec<(usize, usize)> {
+        self.cells()
+            .filter(|cell| self.open_directions(cell.x, cell.y).len() == 1)
+            .map(|cell| cell.to_visited_id())
+            .collect()
+    }
+
+    /// The two cells furthest apart through the maze, the ends of its longest path. Makes a good
+    /// entrance & exit as solving it means walking the whole diameter.
+    pub fn furthest_cells(&self) -> Option<((usize, usize), (usize, usize))> {
+        GraphBuilder::<(usize, usize)>::from(self)
+            .build()
+            .diameter()
+            .map(|(start, end, _)| (start, end))
+    }
+
+    /// Remove dead ends to add loops to the maze, each dead end has a `chance` between 0 & 1 of
+    /// being knocked through. Walls into other dead ends are preferred so one removal fixes two.
+    pub fn braid(&mut self, chance: f64) {
+        self.braid_with(chance, &mut rand::thread_rng());
+    }
+
+    /// `braid` with the random choices taken from `rng`, so a seeded `rng` always braids the same
+    /// way
+    pub fn braid_with<R: Rng>(&mut self, chance: f64, rng: &mut R) {
+        let mut dead_ends = self.dead_ends();
+        dead_ends.shuffle(rng);
+
+        for (x, y) in dead_ends {
+            // An earlier removal may have already opened this cell up
+            if self.open_directions(x, y).len() != 1 || !rng.gen_bool(chance.clamp(0.0, 1.0)) {
+                continue;
+            }
+
+            let cell = self.cell(x, y);
+            let closed = Direction::ALL
+                .into_iter()
+                .filter(|dir| cell.walls[dir.index()])
+                .filter_map(|dir| self.get_neighbor_cell(&cell, dir).map(|n| (dir, n)))
+                .collect::<Vec<_>>();
+            let best = closed
+                .iter()
+                .filter(|(_, n)| self.open_directions(n.x, n.y).len() == 1)
+                .collect::<Vec<_>>();
+
+            let choice = match best.is_empty() {
+                true => closed.choose(rng),
+                false => best.choose(rng).copied(),
+            };
+
+            if let Some((dir, _)) = choice {
+                self.carve(self.cell_id(x, y), *dir);
+            }
+        }
+    }
+
+    /// Turn open passages into one way doors, each one has a `chance` between 0 & 1 of being
+    /// turned. Passages on a shortest route from `entrance` to `exit` only ever point towards the
+    /// exit so it stays reachable, the rest point a random way. Parts of the maze may be cut off
+    /// from the entrance, or be a trap that can be walked into but not out of.
+    pub fn add_one_way_doors(
+        &mut self,
+        chance: f64,
+        entrance: (usize, usize),
+        exit: (usize, usize),
+    ) {
+        self.add_one_way_doors_with(chance, entrance, exit, &mut rand::thread_rng());
+    }
+
+    /// `add_one_way_doors` with the random choices taken from `rng`
+    pub fn add_one_way_doors_with<R: Rng>(
+        &mut self,
+        chance: f64,
+        entrance: (usize, usize),
+        exit: (usize, usize),
+        rng: &mut R,
+    ) {
+        let route = IndexedGraph::from(&*self)
+            .bfs(
+                self.cell_id(entrance.0, entrance.1),
+                self.cell_id(exit.0, exit.1),
+            )
+            .unwrap_or_default();
+
+        // Which way each passage on the route has to point, keyed by its wall slot like `one_way`
+        let mut on_route = HashMap::<(usize, u8), bool>::new();
+
+        for step in route.windows(2) {
+            let (x, y) = (step[0] % self.cols, step[0] / self.cols);
+
+            for dir in Direction::ALL {
+                let next = self.topology.step(x, y, dir, (self.cols, self.rows));
+
+                if next == Some((step[1] % self.cols, step[1] / self.cols))
+                    && self.can_pass(x, y, dir)
+                {
+                    if let Some(slot) = self.wall_slot(x, y, dir) {
+                        on_route.insert(slot, is_forward(dir));
+                    }
+                    break;
+                }
+            }
+        }
+
+        for y in 0..self.rows {
+            for x in 0..self.cols {
+                for dir in [Direction::Right, Direction::Bottom] {
+                    // Tunnels go through standing walls, so they're skipped with them
+                    if self.stored_wall(x, y, dir) || self.is_one_way(x, y, dir) {
+                        continue;
+                    }
+
+                    if !rng.gen_bool(chance.clamp(0.0, 1.0)) {
+                        continue;
+                    }
+
+                    let Some(slot) = self.wall_slot(x, y, dir) else {
+                        continue;
+                    };
+
+                    let forward = match on_route.get(&slot) {
+                        Some(forward) => *forward,
+                        None => rng.gen_bool(0.5),
+                    };
+
+                    self.one_way.insert(slot, forward);
+                }
+            }
+        }
+    }
+
+    /// Check there are walls stored for every cell, every cell with a tunnel under it is a
+    /// crossing, a straight corridor with a cell either side of its closed walls, and every one
+    /// way door is an open wall inside the grid. Each wall is only stored once so it always reads
+    /// the same from both sides. Returns every problem found, apart from a bad size which stops
+    /// the other checks.
+    pub fn validate(&self) -> Result<(), Vec<GridError>> {
+        let count = self.rows * self.cols;
+
+        if self.walls.len() != count.div_ceil(4) {
+            return Err(vec![GridError::BadDimensions {
+                rows: self.rows,
+                cols: self.cols,
+                cells: self.walls.len() * 4,
+            }]);
+        }
+
+        let mut errors = Vec::<GridError>::new();
+        let mut under = self
+            .under
+            .iter()
+            .map(|(id, dir)| (*id, *dir))
+            .collect::<Vec<_>>();
+        under.sort_by_key(|(id, _)| *id);
+
+        for (id, tunnel) in under {
+            let position = (id % self.cols, id / self.cols);
+
+            if id >= count {
+                errors.push(GridError::BadCrossing(position));
+                continue;
+            }
+
+            let cell = self.cell_at(id);
+            let across = tunnel.clockwise();
+            let crossing = cell.walls[tunnel.index()]
+                && cell.walls[tunnel.opposite().index()]
+                && !cell.walls[across.index()]
+                && !cell.walls[across.opposite().index()]
+                && self.get_neighbor_cell(&cell, tunnel).is_some()
+                && self.get_neighbor_cell(&cell, tunnel.opposite()).is_some();
+
+            if !crossing {
+                errors.push(GridError::BadCrossing(position));
+            }
+        }
+
+        let mut doors = self.one_way.keys().copied().collect::<Vec<_>>();
+        doors.sort();
+
+        for (id, bit) in doors {
+            let (x, y) = (id % self.cols, id / self.cols);
+            let dir = match bit {
+                RIGHT_WALL => Direction::Right,
+                _ => Direction::Bottom,
+            };
+
+            let open = id < count
+                && self.wall_slot(x, y, dir) == Some((id, bit))
+                && !self.stored_wall(x, y, dir);
+
+            if !open {
+                errors.push(GridError::BadDoor { cell: (x, y), dir });
+            }
+        }
+
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /// Index of the cell used by `MazeGrid`
+    pub fn cell_id(&self, x: usize, y: usize) -> usize {
+        y * self.cols + x
+    }
+
+    pub fn cell_at(&self, id: usize) -> Cell {
+        self.cell(id % self.cols, id / self.cols)
+    }
+}
+
+impl MazeGrid for Grid {
+    type Direction = Direction;
+
+    fn cell_count(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    fn neighbors(&self, id: usize) -> Vec<(usize, Direction)> {
+        let (x, y) = (id % self.cols, id / self.cols);
+
+        // Stepping straight from the position skips building the walls of every `Cell`
+        Direction::ALL
+            .into_iter()
+            .filter_map(|dir| {
+                self.topology
+                    .step(x, y, dir, (self.cols, self.rows))
+                    .map(|(nx, ny)| (self.cell_id(nx, ny), dir))
+            })
+            .collect()
+    }
+
+    fn carve(&mut self, id: usize, dir: Direction) {
+        self.set_wall(id % self.cols, id / self.cols, dir, false);
+    }
+}
+
+/// Cell level graph of the maze, every cell is a node with an edge through each open wall. A weave
+/// tunnel is an edge between the cells at either end of it, and one way doors are edges that only
+/// go one way.
+impl From<&Grid> for GraphBuilder<(usize, usize)> {
+    fn from(grid: &Grid) -> Self {
+        let mut builder = GraphBuilder::<(usize, usize)>::new();
+
+        for cell in grid.cells() {
+            builder.add_node(cell.to_visited_id());
+        }
+
+        for cell in grid.cells() {
+            for dir in [Direction::Right, Direction::Bottom] {
+                let Some(to) = grid.passage_to(cell.x, cell.y, dir) else {
+                    continue;
+                };
+
+                let from = cell.to_visited_id();
+
+                // When a wrapped side is 2 cells long both walls between a pair of cells can be open
+                if builder.has_edge(&from, &to) {
+                    builder.parallel.push((from, to));
+                }
+
+                // A one way door is an edge in the direction it can be passed
+                match grid.is_one_way(cell.x, cell.y, dir) {
+                    false => builder.add_edge(from, to),
+                    true if grid.can_pass(cell.x, cell.y, dir) => {
+                        builder.add_directed_edge(from, to, 1)
+                    }
+                    true => builder.add_directed_edge(to, from, 1),
+                }
+            }
+        }
+
+        builder
+    }
 }
 
-impl Into<ImageBuffer<Luma<u8>, Vec<u8>>> for Grid {
-    fn into(self) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+impl From<Grid> for ImageBuffer<Luma<u8>, Vec<u8>> {
+    fn from(grid: Grid) -> Self {
         let border_width: u32 = 1;
         let gap: u32 = 1;
         let size: u32 = 1;
-        let cols = self.cols as u32;
-        let rows = self.rows as u32;
+        let cols = grid.cols as u32;
+        let rows = grid.rows as u32;
 
         let image_width = (cols * size) + (border_width * 2) + ((cols - 1) * gap);
         let image_height = (rows * size) + (border_width * 2) + ((rows - 1) * gap);
 
         let mut img: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::new(image_width, image_height);
 
-        for (row, row_vec) in self.matrix.iter().enumerate() {
-            for (col, cell) in row_vec.iter().enumerate() {
-                let top_left_x =
-                    cell.x as u32 + border_width + (gap * col as u32) + ((size - 1) * col as u32);
-                let top_left_y =
-                    cell.y as u32 + border_width + (gap * row as u32) + ((size - 1) * row as u32);
-
-                for x in 0..size {
-                    for y in 0..size {
-                        let pixel = img.get_pixel_mut(top_left_x + x, top_left_y + y);
-                        pixel.0 = [255u8];
+        for cell in grid.cells() {
+            let (col, row) = cell.to_visited_id();
+
+            let top_left_x =
+                cell.x as u32 + border_width + (gap * col as u32) + ((size - 1) * col as u32);
+            let top_left_y =
+                cell.y as u32 + border_width + (gap * row as u32) + ((size - 1) * row as u32);
+
+            for x in 0..size {
+                for y in 0..size {
+                    let pixel = img.get_pixel_mut(top_left_x + x, top_left_y + y);
+                    pixel.0 = [255u8];
+                }
+
+                let s = x;
+
+                for (wall_idx, wall_state) in cell.walls.iter().enumerate() {
+                    if *wall_state {
+                        continue;
                     }
 
-                    let s = x;
+                    // Openings on the outside edge can only come from a wrapped topology
+                    let colour = if grid.is_seam(&cell, Direction::from_index(wall_idx)) {
+                        SEAM_COLOUR
+                    } else {
+                        [255u8]
+                    };
 
-                    for (wall_idx, wall_state) in cell.walls.iter().enumerate() {
-                        if *wall_state {
-                            continue;
-                        }
+                    match wall_idx {
+                        // Top
+                        0 => {
+                            for g in 0..gap {
+                                let offset_y = 1 + g;
+                                let offset_x = s;
 
-                        match wall_idx {
-                            // Top
-                            0 => {
-                                for g in 0..gap {
-                                    let offset_y = 1 + g;
-                                    let offset_x = s;
-
-                                    let pixel = img.get_pixel_mut(
-                                        top_left_x + offset_x,
-                                        top_left_y - offset_y,
-                                    );
-                                    pixel.0 = [255u8];
-                                }
-                            }
-                            // Right
-                            1 => {
-                                for g in 0..gap {
-                                    let offset_x: i32 = s as i32 + (g as i32 - s as i32);
-                                    let offset_y = s;
-
-                                    let pixel = img.get_pixel_mut(
-                                        top_left_x + offset_x as u32 + size,
-                                        top_left_y + offset_y,
-                                    );
-                                    pixel.0 = [255u8];
-                                }
+                                let pixel =
+                                    img.get_pixel_mut(top_left_x + offset_x, top_left_y - offset_y);
+                                pixel.0 = colour;
                             }
-                            // Bottom
-                            2 => {
-                                for g in 0..gap {
-                                    let offset_y = g;
-                                    let offset_x = s;
-
-                                    let pixel = img.get_pixel_mut(
-                                        top_left_x + offset_x,
-                                        top_left_y + offset_y + size,
-                                    );
-                                    pixel.0 = [255u8];
-                                }
+                        }
+                        // Right
+                        1 => {
+                            for g in 0..gap {
+                                let offset_x: i32 = s as i32 + (g as i32 - s as i32);
+                                let offset_y = s;
+
+                                let pixel = img.get_pixel_mut(
+                                    top_left_x + offset_x as u32 + size,
+                                    top_left_y + offset_y,
+                                );
+                                pixel.0 = colour;
                             }
-                            // Left
-                            3 => {
-                                for g in 0..gap {
-                                    let offset_x: i32 = 1 + s as i32 + (g as i32 - s as i32);
-                                    let offset_y = s;
-
-                                    let pixel = img.get_pixel_mut(
-                                        top_left_x - offset_x as u32,
-                                        top_left_y + offset_y,
-                                    );
-                                    pixel.0 = [255u8];
-                                }
+                        }
+                        // Bottom
+                        2 => {
+                            for g in 0..gap {
+                                let offset_y = g;
+                                let offset_x = s;
+
+                                let pixel = img.get_pixel_mut(
+                                    top_left_x + offset_x,
+                                    top_left_y + offset_y + size,
+                                );
+                                pixel.0 = colour;
                             }
-                            _ => {
-                                println!("Well this shouldn't happen");
+                        }
+                        // Left
+                        3 => {
+                            for g in 0..gap {
+                                let offset_x: i32 = 1 + s as i32 + (g as i32 - s as i32);
+                                let offset_y = s;
+
+                                let pixel = img.get_pixel_mut(
+                                    top_left_x - offset_x as u32,
+                                    top_left_y + offset_y,
+                                );
+                                pixel.0 = colour;
                             }
-                        };
-                    }
+                        }
+                        _ => {
+                            println!("Well this shouldn't happen");
+                        }
+                    };
                 }
             }
         }
@@ -171,3 +827,178 @@ impl Into<ImageBuffer<Luma<u8>, Vec<u8>>> for Grid {
         img
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_plane_has_no_wrapping() {
+        let grid = Grid::new(3, 4);
+        let corner = grid.cell(0, 0);
+
+        assert!(grid.get_neighbor_cell(&corner, Direction::Top).is_none());
+        assert!(grid.get_neighbor_cell(&corner, Direction::Left).is_none());
+        assert_eq!(3, grid.manhattan_distance((0, 0), (3, 0)));
+        assert!(!grid.is_seam(&corner, Direction::Left));
+
+        // A cylinder only wraps its sides
+        let grid = Grid::with_topology(3, 4, Topology::Cylinder);
+        assert!(grid.is_seam(&corner, Direction::Left));
+        assert!(!grid.is_seam(&corner, Direction::Top));
+    }
+
+    #[test]
+    fn test_mobius_flips_rows_across_seam() {
+        let grid = Grid::with_topology(3, 4, Topology::Mobius);
+        let cell = grid.cell(3, 0);
+
+        let neighbor = grid.get_neighbor_cell(&cell, Direction::Right).unwrap();
+        assert_eq!((0, 2), neighbor.to_visited_id());
+        assert_eq!(1, grid.manhattan_distance((3, 0), (0, 2)));
+        assert!(grid.get_neighbor_cell(&cell, Direction::Top).is_none());
+    }
+
+    #[test]
+    fn test_torus_distance_wraps_both_axes() {
+        let grid = Grid::with_topology(10, 10, Topology::Torus);
+
+        assert_eq!(2, grid.manhattan_distance((0, 0), (9, 9)));
+    }
+
+    #[test]
+    fn test_full_braid_removes_every_dead_end() {
+        use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+
+        let mut maze = RandomisedDFS::from_grid_size(10, 10);
+        maze.generate();
+        assert!(!maze.grid.dead_ends().is_empty());
+
+        maze.grid.braid(1.0);
+        assert!(maze.grid.dead_ends().is_empty());
+    }
+
+    #[test]
+    fn test_walls_are_shared_with_neighbours() {
+        let mut grid = Grid::with_topology(3, 4, Topology::Mobius);
+
+        // The left wall of (0, 0) is the right wall of the bottom right cell across the seam
+        grid.set_wall(0, 0, Direction::Left, false);
+        assert!(!grid.has_wall(3, 2, Direction::Right));
+        assert!(grid.has_wall(3, 0, Direction::Right));
+
+        grid.carve(grid.cell_id(1, 1), Direction::Top);
+        assert!(!grid.cell(1, 0).walls[Direction::Bottom.index()]);
+
+        // The outside of a plane can't be opened
+        let mut grid = Grid::new(3, 3);
+        grid.set_wall(0, 0, Direction::Top, false);
+        assert!(grid.has_wall(0, 0, Direction::Top));
+    }
+
+    #[test]
+    fn test_one_way_doors() {
+        use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut grid = Grid::with_topology(3, 4, Topology::Mobius);
+
+        // Across the seam, leaving (0, 0) to the left lands on the bottom right cell
+        grid.set_one_way(0, 0, Direction::Left);
+        grid.set_one_way(1, 1, Direction::Top);
+
+        assert!(grid.can_pass(0, 0, Direction::Left));
+        assert!(!grid.can_pass(3, 2, Direction::Right));
+        assert!(grid.is_one_way(3, 2, Direction::Right));
+        assert!(!grid.has_wall(3, 2, Direction::Right));
+        assert_eq!(
+            vec![((0, 0), Direction::Left), ((1, 1), Direction::Top)],
+            grid.one_way_doors()
+        );
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&grid).build();
+        assert!(graph.is_directed());
+        assert_eq!(Some(1), graph.weight(&(0, 0), &(3, 2)));
+        assert_eq!(None, graph.weight(&(3, 2), &(0, 0)));
+
+        // Putting the wall back up removes the door
+        grid.set_wall(1, 0, Direction::Bottom, true);
+        assert_eq!(vec![((0, 0), Direction::Left)], grid.one_way_doors());
+
+        for seed in 0..20 {
+            let mut maze = RandomisedDFS::from_grid_size(8, 8).with_seed(seed);
+            maze.generate();
+            maze.grid.braid_with(0.5, &mut StdRng::seed_from_u64(seed));
+            maze.grid
+                .add_one_way_doors_with(0.7, (0, 0), (7, 7), &mut StdRng::seed_from_u64(seed));
+
+            let graph = GraphBuilder::<(usize, usize)>::from(&maze.grid).build();
+            assert!(graph.bfs((0, 0), (7, 7)).is_some());
+            assert!(!maze.grid.one_way_doors().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_validate_finds_bad_crossings() {
+        let mut grid = Grid::new(3, 3);
+        grid.carve(grid.cell_id(1, 1), Direction::Top);
+        grid.carve(grid.cell_id(1, 1), Direction::Bottom);
+        grid.set_under(1, 1, Some(Direction::Left));
+
+        // A tunnel under the middle cell opens the walls of the cells either side
+        assert_eq!(Ok(()), grid.validate());
+        assert!(!grid.has_wall(0, 1, Direction::Right));
+        assert!(grid.has_wall(1, 1, Direction::Left));
+
+        // A tunnel can't come out past the edge of the grid
+        grid.carve(grid.cell_id(1, 0), Direction::Right);
+        grid.set_under(2, 0, Some(Direction::Bottom));
+
+        let errors = grid.validate().unwrap_err();
+        assert_eq!(vec![GridError::BadCrossing((2, 0))], errors);
+        assert_eq!(
+            "cell (2, 0) has a tunnel under it but isn't a crossing",
+            errors[0].to_string()
+        );
+        grid.set_under(2, 0, None);
+
+        // Doors have to be open walls inside the grid
+        grid.set_one_way(0, 0, Direction::Right);
+        grid.one_way.insert((grid.cell_id(0, 2), BOTTOM_WALL), true);
+        grid.one_way.insert((grid.cell_id(2, 2), RIGHT_WALL), true);
+
+        let errors = grid.validate().unwrap_err();
+        assert_eq!(
+            vec![
+                GridError::BadDoor {
+                    cell: (0, 2),
+                    dir: Direction::Bottom,
+                },
+                GridError::BadDoor {
+                    cell: (2, 2),
+                    dir: Direction::Right,
+                },
+            ],
+            errors
+        );
+        assert_eq!(
+            "one way door on Bottom of (0, 2) isn't an open wall",
+            errors[0].to_string()
+        );
+        grid.one_way.clear();
+
+        grid.rows = 5;
+        assert_eq!(
+            Err(vec![GridError::BadDimensions {
+                rows: 5,
+                cols: 3,
+                cells: 12,
+            }]),
+            grid.validate()
+        );
+        assert_eq!(
+            "grid should be 5 rows of 3 cells, but has walls for 12 cells",
+            grid.validate().unwrap_err()[0].to_string()
+        );
+    }
+}
@@ -0,0 +1,318 @@
+use super::grid::Grid;
+use super::maze::{Direction, MazeGrid};
+use crate::graph::builder::GraphBuilder;
+use image::{imageops, ImageBuffer, Luma};
+use std::convert::From;
+
+/// Colour of a cell with a staircase leading up a level
+pub const STAIRS_UP_COLOUR: [u8; 1] = [170u8];
+/// Colour of a cell with a staircase leading down a level
+pub const STAIRS_DOWN_COLOUR: [u8; 1] = [70u8];
+/// Colour of a cell with staircases leading both up & down
+pub const STAIRS_BOTH_COLOUR: [u8; 1] = [120u8];
+/// Pixels of empty space between each level in the rendered image
+const LEVEL_SPACING: u32 = 2;
+
+/// (x, y, z)
+pub type Point3D = (usize, usize, usize);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Direction3D {
+    /// A direction within the same level
+    Flat(Direction),
+    Up,
+    Down,
+}
+
+impl Direction3D {
+    pub const ALL: [Direction3D; 6] = [
+        Direction3D::Flat(Direction::Top),
+        Direction3D::Flat(Direction::Right),
+        Direction3D::Flat(Direction::Bottom),
+        Direction3D::Flat(Direction::Left),
+        Direction3D::Up,
+        Direction3D::Down,
+    ];
+
+    /// Index of the wall on this side in `Cell3D::walls`
+    pub fn index(&self) -> usize {
+        match self {
+            Direction3D::Flat(dir) => dir.index(),
+            Direction3D::Up => 4,
+            Direction3D::Down => 5,
+        }
+    }
+
+    pub fn opposite(&self) -> Direction3D {
+        match self {
+            Direction3D::Flat(dir) => Direction3D::Flat(dir.opposite()),
+            Direction3D::Up => Direction3D::Down,
+            Direction3D::Down => Direction3D::Up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct Cell3D {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+    /// [top, right, bottom, left, up, down]
+    pub walls: [bool; 6],
+}
+
+impl Cell3D {
+    pub fn new(x: usize, y: usize, z: usize) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            walls: [true; 6],
+        }
+    }
+
+    pub fn to_visited_id(self) -> Point3D {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn has_stairs_up(&self) -> bool {
+        !self.walls[Direction3D::Up.index()]
+    }
+
+    pub fn has_stairs_down(&self) -> bool {
+        !self.walls[Direction3D::Down.index()]
+    }
+}
+
+/// A stack of 2D levels, cells can be joined to the cell directly above or below them by a staircase
+#[derive(Debug, Clone)]
+pub struct Grid3D {
+    pub cols: usize,
+    pub rows: usize,
+    pub levels: usize,
+    /// Indexed by `[z][y][x]`
+    pub matrix: Vec<Vec<Vec<Cell3D>>>,
+}
+
+impl Grid3D {
+    pub fn new(rows: usize, cols: usize, levels: usize) -> Self {
+        let matrix = (0..levels)
+            .map(|z| {
+                (0..rows)
+                    .map(|y| (0..cols).map(|x| Cell3D::new(x, y, z)).collect())
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            cols,
+            rows,
+            levels,
+            matrix,
+        }
+    }
+
+    pub fn get_neighbor_cell(&self, starting_cell: &Cell3D, dir: Direction3D) -> Option<Cell3D> {
+        let Cell3D { x, y, z, .. } = *starting_cell;
+
+        let (x, y, z) = match dir {
+            Direction3D::Flat(Direction::Top) if y > 0 => (x, y - 1, z),
+            Direction3D::Flat(Direction::Right) if x + 1 < self.cols => (x + 1, y, z),
+            Direction3D::Flat(Direction::Bottom) if y + 1 < self.rows => (x, y + 1, z),
+            Direction3D::Flat(Direction::Left) if x > 0 => (x - 1, y, z),
+            Direction3D::Up if z + 1 < self.levels => (x, y, z + 1),
+            Direction3D::Down if z > 0 => (x, y, z - 1),
+            _ => return None,
+        };
+
+        Some(self.matrix[z][y][x])
+    }
+
+    pub fn cell_id(&self, x: usize, y: usize, z: usize) -> usize {
+        (z * self.rows + y) * self.cols + x
+    }
+
+    pub fn cell_at(&self, id: usize) -> Cell3D {
+        let level_size = self.rows * self.cols;
+        let z = id / level_size;
+        let y = (id % level_size) / self.cols;
+        let x = id % self.cols;
+
+        self.matrix[z][y][x]
+    }
+
+    /// Manhattan distance between two cells, each staircase counts as a single step
+    pub fn manhattan_distance(&self, from: Point3D, to: Point3D) -> usize {
+        from.0.abs_diff(to.0) + from.1.abs_diff(to.1) + from.2.abs_diff(to.2)
+    }
+
+    /// A single level as a 2D grid, staircases are dropped
+    pub fn level(&self, z: usize) -> Grid {
+        let mut grid = Grid::new(self.rows, self.cols);
+
+        for (y, row) in self.matrix[z].iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                for dir in Direction::ALL {
+                    grid.set_wall(x, y, dir, cell.walls[dir.index()]);
+                }
+            }
+        }
+
+        grid
+    }
+
+    /// Human readable directions through the maze for a path of cells, one step per line.
+    /// Staircases are called out as they change level.
+    pub fn solution_listing(&self, path: &[Point3D]) -> String {
+        let mut lines = Vec::<String>::with_capacity(path.len());
+
+        for (idx, point) in path.iter().enumerate() {
+            let (x, y, z) = *point;
+
+            let step = match idx.checked_sub(1).map(|prev| path[prev].2) {
+                Some(prev_z) if prev_z < z => format!("stairs up to level {}, ({}, {})", z, x, y),
+                Some(prev_z) if prev_z > z => {
+                    format!("stairs down to level {}, ({}, {})", z, x, y)
+                }
+                _ => format!("level {}, ({}, {})", z, x, y),
+            };
+
+            lines.push(format!("{:>4}: {}", idx, step));
+        }
+
+        lines.join("\n")
+    }
+
+    pub fn generate_as_image(self) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+        let image: ImageBuffer<Luma<u8>, Vec<u8>> = self.into();
+        image
+    }
+}
+
+impl MazeGrid for Grid3D {
+    type Direction = Direction3D;
+
+    fn cell_count(&self) -> usize {
+        self.levels * self.rows * self.cols
+    }
+
+    fn neighbors(&self, id: usize) -> Vec<(usize, Direction3D)> {
+        let cell = self.cell_at(id);
+
+        Direction3D::ALL
+            .into_iter()
+            .filter_map(|dir| {
+                self.get_neighbor_cell(&cell, dir)
+                    .map(|n| (self.cell_id(n.x, n.y, n.z), dir))
+            })
+            .collect()
+    }
+
+    fn carve(&mut self, id: usize, dir: Direction3D) {
+        let cell = self.cell_at(id);
+
+        if let Some(n) = self.get_neighbor_cell(&cell, dir) {
+            self.matrix[cell.z][cell.y][cell.x].walls[dir.index()] = false;
+            self.matrix[n.z][n.y][n.x].walls[dir.opposite().index()] = false;
+        }
+    }
+}
+
+/// Cell level graph of the maze, staircases are edges between levels
+impl From<&Grid3D> for GraphBuilder<Point3D> {
+    fn from(grid: &Grid3D) -> Self {
+        let mut builder = GraphBuilder::<Point3D>::new();
+        let cells = grid.matrix.iter().flatten().flatten();
+
+        for cell in cells.clone() {
+            builder.add_node(cell.to_visited_id());
+        }
+
+        for cell in cells {
+            for dir in [
+                Direction3D::Flat(Direction::Right),
+                Direction3D::Flat(Direction::Bottom),
+                Direction3D::Up,
+            ] {
+                if cell.walls[dir.index()] {
+                    continue;
+                }
+
+                if let Some(neighbor) = grid.get_neighbor_cell(cell, dir) {
+                    builder.add_edge(cell.to_visited_id(), neighbor.to_visited_id());
+                }
+            }
+        }
+
+        builder
+    }
+}
+
+/// Each level is drawn side by side from left to right, starting with level 0.
+/// Cells with staircases are shaded with the `STAIRS_*` colours.
+impl From<Grid3D> for ImageBuffer<Luma<u8>, Vec<u8>> {
+    fn from(grid: Grid3D) -> Self {
+        let levels: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> =
+            (0..grid.levels).map(|z| grid.level(z).into()).collect();
+
+        let (level_width, level_height) = levels.first().map_or((0, 0), |l| l.dimensions());
+        let count = levels.len() as u32;
+        let image_width = (level_width * count) + (LEVEL_SPACING * count.saturating_sub(1));
+
+        let mut img: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::new(image_width, level_height);
+
+        for (z, level) in levels.iter().enumerate() {
+            let offset_x = z as u32 * (level_width + LEVEL_SPACING);
+            imageops::replace(&mut img, level, offset_x as i64, 0);
+
+            for cell in grid.matrix[z].iter().flatten() {
+                let colour = match (cell.has_stairs_up(), cell.has_stairs_down()) {
+                    (true, true) => STAIRS_BOTH_COLOUR,
+                    (true, false) => STAIRS_UP_COLOUR,
+                    (false, true) => STAIRS_DOWN_COLOUR,
+                    (false, false) => continue,
+                };
+
+                // Matches the 1px cell, border & gap layout of the 2D renderer
+                let pixel =
+                    img.get_pixel_mut(offset_x + 1 + cell.x as u32 * 2, 1 + cell.y as u32 * 2);
+                pixel.0 = colour;
+            }
+        }
+
+        img
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+
+    #[test]
+    fn test_generates_a_connected_3d_maze() {
+        let mut maze = RandomisedDFS::from_grid(Grid3D::new(4, 5, 3));
+        maze.generate();
+
+        let graph = GraphBuilder::<Point3D>::from(&maze.grid).build();
+        let path = graph.bfs((0, 0, 0), (4, 3, 2)).unwrap();
+
+        assert_eq!(60, graph.len());
+        assert!(path.contains_key(&(4, 3, 2)));
+        assert!(maze.grid.matrix[1]
+            .iter()
+            .flatten()
+            .any(|c| c.has_stairs_down()));
+    }
+
+    #[test]
+    fn test_solution_listing_calls_out_stairs() {
+        let grid = Grid3D::new(2, 2, 2);
+        let listing = grid.solution_listing(&[(0, 0, 0), (0, 0, 1), (1, 0, 1), (1, 0, 0)]);
+
+        assert_eq!(
+            "   0: level 0, (0, 0)\n   1: stairs up to level 1, (0, 0)\n   2: level 1, (1, 0)\n   3: stairs down to level 0, (1, 0)",
+            listing
+        );
+    }
+}
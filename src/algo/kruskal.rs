@@ -0,0 +1,172 @@
+use rand::seq::SliceRandom;
+
+use super::grid::Grid;
+use super::maze::{MazeGenerate, MazeGrid};
+use super::observer::GenerationObserver;
+use crate::util::{seeded_rng, DisjointSet};
+
+/// Based off this description:
+/// [Wikipedia](https://en.wikipedia.org/wiki/Maze_generation_algorithm#Iterative_randomized_Kruskal's_algorithm_(with_sets))
+/// Every wall is visited in a random order and removed if the cells either side of it aren't
+/// joined yet, with a `DisjointSet` keeping track of which cells are joined.
+pub struct RandomisedKruskal<G: MazeGrid = Grid> {
+    pub grid: G,
+    /// Seed for shuffling the walls & placing any crossings `MazeGrid::pre_carve` makes, `None`
+    /// gives a different maze every time
+    pub seed: Option<u64>,
+}
+
+impl RandomisedKruskal<Grid> {
+    pub fn from_grid_size(rows: usize, cols: usize) -> Self {
+        Self {
+            grid: Grid::new(rows, cols),
+            seed: None,
+        }
+    }
+}
+
+impl<G: MazeGrid> RandomisedKruskal<G> {
+    pub fn from_grid(grid: G) -> Self {
+        Self { grid, seed: None }
+    }
+
+    /// Make generation repeatable, the same grid & seed always give the same maze
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl Default for RandomisedKruskal<Grid> {
+    fn default() -> Self {
+        Self {
+            grid: Grid::new(10, 10),
+            seed: None,
+        }
+    }
+}
+
+/// Kruskal has no notion of visiting a cell or backtracking, so observers only get told about
+/// carves and the number of walls left to check
+impl<G: MazeGrid> MazeGenerate for RandomisedKruskal<G> {
+    type Direction = G::Direction;
+
+    fn generate_with(&mut self, observer: &mut dyn GenerationObserver<G::Direction>) {
+        self.steps_with(observer).for_each(drop);
+    }
+
+    fn steps(&mut self) -> impl Iterator<Item = (usize, G::Direction)> + '_ {
+        self.steps_with(())
+    }
+}
+
+impl<G: MazeGrid> RandomisedKruskal<G> {
+    /// Step through generation, reporting each step to `observer` as it happens. Passages from
+    /// `MazeGrid::pre_carve` are carved straight away.
+    pub fn steps_with<O: GenerationObserver<G::Direction>>(
+        &mut self,
+        mut observer: O,
+    ) -> RandomisedKruskalSteps<'_, G, O> {
+        let mut rng = seeded_rng(self.seed);
+        let mut sets = DisjointSet::new(self.grid.cell_count());
+
+        self.grid.pre_carve(&mut rng, &mut sets);
+
+        // Create a list of all walls, each wall is only listed from the cell with the lower index
+        let mut walls = (0..self.grid.cell_count())
+            .flat_map(|cell| {
+                self.grid
+                    .neighbors(cell)
+                    .into_iter()
+                    .filter(move |(neighbor, _)| *neighbor > cell)
+                    .map(move |(neighbor, dir)| (cell, neighbor, dir))
+            })
+            .collect::<Vec<_>>();
+
+        walls.shuffle(&mut rng);
+        observer.frontier_changed(walls.len());
+
+        RandomisedKruskalSteps {
+            grid: &mut self.grid,
+            observer,
+            sets,
+            walls,
+        }
+    }
+}
+
+/// Kruskal part way through. Each call to `next` takes walls off the shuffled list until one
+/// divides two cells that aren't joined yet and removes it. Dropping it leaves the grid as
+/// separate patches of passages that haven't been joined up.
+pub struct RandomisedKruskalSteps<'a, G: MazeGrid, O> {
+    grid: &'a mut G,
+    observer: O,
+    sets: DisjointSet,
+    /// Walls left to check, taken from the end
+    walls: Vec<(usize, usize, G::Direction)>,
+}
+
+impl<G: MazeGrid, O: GenerationObserver<G::Direction>> Iterator
+    for RandomisedKruskalSteps<'_, G, O>
+{
+    type Item = (usize, G::Direction);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((cell, neighbor, dir)) = self.walls.pop() {
+            self.observer.frontier_changed(self.walls.len());
+
+            // If the cells divided by this wall belong to distinct sets, remove the wall & join them
+            if self.grid.can_carve(cell, dir) && self.sets.union(cell, neighbor) {
+                self.grid.carve(cell, dir);
+                self.observer.carved(cell, dir, neighbor);
+
+                return Some((cell, dir));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::grid3d::Grid3D;
+    use crate::graph::builder::GraphBuilder;
+
+    #[test]
+    fn test_kruskal_builds_a_perfect_maze() {
+        let mut maze = RandomisedKruskal::from_grid_size(8, 12);
+        maze.generate();
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&maze.grid).build();
+        assert_eq!(95, graph.edge_count());
+        assert!(graph.bfs((0, 0), (11, 7)).is_some());
+    }
+
+    #[test]
+    fn test_cancelled_steps_leave_grid_partially_carved() {
+        let mut maze = RandomisedKruskal::from_grid_size(6, 6);
+        let carves = maze.steps().take(10).collect::<Vec<_>>();
+
+        let open = maze
+            .grid
+            .cells()
+            .flat_map(|c| c.walls)
+            .filter(|w| !w)
+            .count();
+
+        assert_eq!(10, carves.len());
+        assert_eq!(20, open);
+    }
+
+    #[test]
+    fn test_kruskal_works_in_3d() {
+        let mut maze = RandomisedKruskal::from_grid(Grid3D::new(3, 3, 3));
+        maze.generate();
+
+        let graph = GraphBuilder::<(usize, usize, usize)>::from(&maze.grid).build();
+
+        assert!(graph.bfs((0, 0, 0), (2, 2, 2)).is_some());
+    }
+}
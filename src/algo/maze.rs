@@ -1,8 +1,58 @@
+use rand::RngCore;
+
+use super::observer::GenerationObserver;
+use crate::util::DisjointSet;
+
 pub trait MazeGenerate {
-    fn generate(&mut self);
+    /// Direction type of the grid being generated, passed along to observers
+    type Direction;
+
+    fn generate(&mut self) {
+        self.generate_with(&mut ());
+    }
+
+    /// Generate the maze, telling `observer` about each step as it happens
+    fn generate_with(&mut self, observer: &mut dyn GenerationObserver<Self::Direction>);
+
+    /// Generate one removed wall at a time, as (cell id, direction of the wall). Generation can
+    /// be paused by not calling `next` and cancelled by dropping the iterator.
+    fn steps(&mut self) -> impl Iterator<Item = (usize, Self::Direction)> + '_;
 }
 
-#[derive(Debug, Copy, Clone)]
+/// A grid of cells that generators can carve passages through.
+///
+/// Cells are addressed by an index so a generator doesn't need to know the shape of the grid,
+/// letting the same generator work on 2D and 3D grids.
+pub trait MazeGrid {
+    type Direction: Copy;
+
+    fn cell_count(&self) -> usize;
+
+    /// Every cell next to the cell at `id` and the direction it is in
+    fn neighbors(&self, id: usize) -> Vec<(usize, Self::Direction)>;
+
+    /// Remove the wall on `dir` of the cell at `id`, along with the matching wall of its neighbour
+    fn carve(&mut self, id: usize, dir: Self::Direction);
+
+    /// Whether the move in `dir` from the cell at `id` can still be carved. Generators that
+    /// decide on their moves up front (e.g. Kruskal) check this first, as earlier carves can rule
+    /// a move out on some grids.
+    fn can_carve(&self, _id: usize, _dir: Self::Direction) -> bool {
+        true
+    }
+
+    /// Relative chance of a generator picking a move in `dir` over the other moves available,
+    /// moves with a preference of 0 are never picked
+    fn preference(&self, _dir: Self::Direction) -> f64 {
+        1.0
+    }
+
+    /// Carve any passages that need to exist before a set based generator (e.g. Kruskal) joins
+    /// up the rest of the grid. Cells joined here must also be joined in `sets`.
+    fn pre_carve(&mut self, _rng: &mut dyn RngCore, _sets: &mut DisjointSet) {}
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum Direction {
     Top,
     Right,
@@ -11,6 +61,38 @@ pub enum Direction {
 }
 
 impl Direction {
+    /// Every direction, in the same order as `Cell::walls`
+    pub const ALL: [Direction; 4] = [
+        Direction::Top,
+        Direction::Right,
+        Direction::Bottom,
+        Direction::Left,
+    ];
+
+    /// Index of the wall on this side in `Cell::walls`
+    pub fn index(&self) -> usize {
+        match self {
+            Direction::Top => 0,
+            Direction::Right => 1,
+            Direction::Bottom => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    pub fn from_index(index: usize) -> Direction {
+        Direction::ALL[index % 4]
+    }
+
+    /// The direction after a quarter turn to the right
+    pub fn clockwise(&self) -> Direction {
+        Direction::from_index(self.index() + 1)
+    }
+
+    /// The direction after a quarter turn to the left
+    pub fn anticlockwise(&self) -> Direction {
+        Direction::from_index(self.index() + 3)
+    }
+
     pub fn opposite(&self) -> Direction {
         match self {
             Direction::Top => Direction::Bottom,
@@ -27,6 +109,8 @@ pub struct Cell {
     pub y: usize,
     /// [top, right, bottom, left]
     pub walls: [bool; 4],
+    /// A passage tunnels underneath this cell, perpendicular to the corridor running over it
+    pub under: bool,
 }
 
 impl Cell {
@@ -35,10 +119,11 @@ impl Cell {
             x,
             y,
             walls: [true, true, true, true],
+            under: false,
         }
     }
 
-    pub fn to_visited_id(&self) -> (usize, usize) {
+    pub fn to_visited_id(self) -> (usize, usize) {
         (self.x, self.y)
     }
 }
@@ -0,0 +1,123 @@
+/// Hooks a generator calls as it runs, every method does nothing by default so an observer only
+/// needs to implement the events it cares about. `D` is the direction type of the grid.
+///
+/// Passages a grid carves for itself in `MazeGrid::pre_carve` aren't reported.
+pub trait GenerationObserver<D> {
+    /// The generator reached `cell` for the first time
+    fn visited(&mut self, _cell: usize) {}
+
+    /// The wall on `dir` of `cell` was removed, joining it to `neighbor`
+    fn carved(&mut self, _cell: usize, _dir: D, _neighbor: usize) {}
+
+    /// The generator gave up on `cell` as it has nowhere left to go
+    fn backtracked(&mut self, _cell: usize) {}
+
+    /// The number of cells or walls still waiting to be processed changed, e.g. the size of the
+    /// stack for Randomised DFS or the walls left to check for Kruskal
+    fn frontier_changed(&mut self, _size: usize) {}
+}
+
+/// Ignores every event, used by `MazeGenerate::generate`
+impl<D> GenerationObserver<D> for () {}
+
+impl<D, O: GenerationObserver<D> + ?Sized> GenerationObserver<D> for &mut O {
+    fn visited(&mut self, cell: usize) {
+        (**self).visited(cell);
+    }
+
+    fn carved(&mut self, cell: usize, dir: D, neighbor: usize) {
+        (**self).carved(cell, dir, neighbor);
+    }
+
+    fn backtracked(&mut self, cell: usize) {
+        (**self).backtracked(cell);
+    }
+
+    fn frontier_changed(&mut self, size: usize) {
+        (**self).frontier_changed(size);
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum GenerationEvent<D> {
+    Visited(usize),
+    Carved {
+        cell: usize,
+        dir: D,
+        neighbor: usize,
+    },
+    Backtracked(usize),
+    FrontierChanged(usize),
+}
+
+/// Keep every event in the order it happened
+impl<D> GenerationObserver<D> for Vec<GenerationEvent<D>> {
+    fn visited(&mut self, cell: usize) {
+        self.push(GenerationEvent::Visited(cell));
+    }
+
+    fn carved(&mut self, cell: usize, dir: D, neighbor: usize) {
+        self.push(GenerationEvent::Carved {
+            cell,
+            dir,
+            neighbor,
+        });
+    }
+
+    fn backtracked(&mut self, cell: usize) {
+        self.push(GenerationEvent::Backtracked(cell));
+    }
+
+    fn frontier_changed(&mut self, size: usize) {
+        self.push(GenerationEvent::FrontierChanged(size));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::Direction, maze::MazeGenerate, RandomisedDFS, RandomisedKruskal};
+
+    fn count(
+        events: &[GenerationEvent<Direction>],
+        f: fn(&GenerationEvent<Direction>) -> bool,
+    ) -> usize {
+        events.iter().filter(|e| f(e)).count()
+    }
+
+    #[test]
+    fn test_dfs_reports_every_cell() {
+        let mut maze = RandomisedDFS::from_grid_size(4, 5);
+        let mut events = Vec::<GenerationEvent<Direction>>::new();
+        maze.generate_with(&mut events);
+
+        assert_eq!(GenerationEvent::Visited(0), events[0]);
+        assert_eq!(
+            20,
+            count(&events, |e| matches!(e, GenerationEvent::Visited(_)))
+        );
+        assert_eq!(
+            19,
+            count(&events, |e| matches!(e, GenerationEvent::Carved { .. }))
+        );
+        // Every cell is eventually popped off the stack with nowhere left to go
+        assert_eq!(
+            20,
+            count(&events, |e| matches!(e, GenerationEvent::Backtracked(_)))
+        );
+        assert_eq!(Some(&GenerationEvent::FrontierChanged(0)), events.last());
+    }
+
+    #[test]
+    fn test_kruskal_reports_carves() {
+        let mut maze = RandomisedKruskal::from_grid_size(4, 5);
+        let mut events = Vec::<GenerationEvent<Direction>>::new();
+        maze.generate_with(&mut events);
+
+        assert_eq!(
+            19,
+            count(&events, |e| matches!(e, GenerationEvent::Carved { .. }))
+        );
+        assert_eq!(Some(&GenerationEvent::FrontierChanged(0)), events.last());
+    }
+}
@@ -0,0 +1,318 @@
+use rand::{seq::SliceRandom, Rng};
+use std::collections::VecDeque;
+use std::thread;
+
+use super::grid::Grid;
+use super::maze::{Direction, MazeGenerate, MazeGrid};
+use super::observer::GenerationObserver;
+use super::randomised_dfs::RandomisedDFS;
+use crate::util::{seeded_rng, DisjointSet};
+
+/// Generates very large plane mazes on every core. The grid is cut into square tiles, each tile
+/// gets its own Randomised DFS maze on a separate thread, then a Kruskal pass over the tiles
+/// knocks one wall through the boundary between each pair of tiles it joins. Every tile is a
+/// perfect maze and the tiles are joined in a spanning tree, so the whole maze is perfect.
+///
+/// Each tile is seeded from the seed & its position, so the same seed gives the same maze no
+/// matter how many threads are used. Long corridors stay inside a tile, larger tiles look more
+/// like a plain Randomised DFS maze.
+pub struct ParallelTiled {
+    pub grid: Grid,
+    /// Width & height of each tile in cells, tiles on the right & bottom edges may be smaller
+    pub tile_size: usize,
+    /// Number of threads generating tiles at once
+    pub threads: usize,
+    /// Seed each tile's seed is mixed from, it also picks how the tiles are joined. `None` gives a
+    /// different maze every time.
+    pub seed: Option<u64>,
+}
+
+impl ParallelTiled {
+    pub fn from_grid_size(rows: usize, cols: usize) -> Self {
+        Self {
+            grid: Grid::new(rows, cols),
+            tile_size: 256,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            seed: None,
+        }
+    }
+
+    /// Make generation repeatable, the same grid size, tile size & seed always give the same maze
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn layout(&self) -> TileLayout {
+        let size = self.tile_size.max(1);
+
+        TileLayout {
+            size,
+            rows: self.grid.rows,
+            cols: self.grid.cols,
+            across: self.grid.cols.div_ceil(size),
+            down: self.grid.rows.div_ceil(size),
+        }
+    }
+
+    /// Generate the maze of every tile, spread over `threads` threads
+    fn generate_tiles(&self, seed: u64) -> Vec<Tile> {
+        let layout = self.layout();
+        let count = layout.count();
+        let threads = self.threads.clamp(1, count.max(1));
+
+        let mut tiles = thread::scope(|scope| {
+            let handles = (0..threads)
+                .map(|first| {
+                    scope.spawn(move || {
+                        (first..count)
+                            .step_by(threads)
+                            .map(|index| layout.generate(index, tile_seed(seed, index)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().expect("tile generation panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        tiles.sort_by_key(|tile| tile.index);
+        tiles
+    }
+}
+
+impl Default for ParallelTiled {
+    fn default() -> Self {
+        Self::from_grid_size(10, 10)
+    }
+}
+
+/// Tiles are generated up front, observers are then told about the carves one tile at a time
+/// followed by the walls joining the tiles, rather than in the order they were made
+impl MazeGenerate for ParallelTiled {
+    type Direction = Direction;
+
+    fn generate_with(&mut self, observer: &mut dyn GenerationObserver<Direction>) {
+        self.steps_with(observer).for_each(drop);
+    }
+
+    fn steps(&mut self) -> impl Iterator<Item = (usize, Direction)> + '_ {
+        self.steps_with(())
+    }
+}
+
+impl ParallelTiled {
+    /// Generate every tile, then step through copying them into the grid & joining them up,
+    /// reporting each carve to `observer`
+    pub fn steps_with<O: GenerationObserver<Direction>>(
+        &mut self,
+        observer: O,
+    ) -> ParallelTiledSteps<'_, O> {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let layout = self.layout();
+        let tiles = self.generate_tiles(seed);
+        let mut joins = layout.joins(seed);
+
+        // Taken from the end, so reverse to carve them in the order they were picked
+        joins.reverse();
+
+        ParallelTiledSteps {
+            grid: &mut self.grid,
+            observer,
+            tiles: VecDeque::from(tiles),
+            slot: 0,
+            joins,
+        }
+    }
+}
+
+/// Mix the seed with the index of a tile, so neighbouring tiles don't get related seeds
+fn tile_seed(seed: u64, index: usize) -> u64 {
+    // SplitMix64
+    let mut z = seed.wrapping_add((index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// How the grid is cut into tiles, tiles are numbered in row-major order
+#[derive(Debug, Clone, Copy)]
+struct TileLayout {
+    size: usize,
+    rows: usize,
+    cols: usize,
+    across: usize,
+    down: usize,
+}
+
+impl TileLayout {
+    fn count(&self) -> usize {
+        self.across * self.down
+    }
+
+    /// Top left cell, width & height of a tile
+    fn bounds(&self, index: usize) -> (usize, usize, usize, usize) {
+        let (x, y) = (
+            index % self.across * self.size,
+            index / self.across * self.size,
+        );
+
+        (
+            x,
+            y,
+            self.size.min(self.cols - x),
+            self.size.min(self.rows - y),
+        )
+    }
+
+    fn generate(&self, index: usize, seed: u64) -> Tile {
+        let (x, y, width, height) = self.bounds(index);
+        let mut maze = RandomisedDFS::from_grid_size(height, width).with_seed(seed);
+        maze.generate();
+
+        Tile {
+            index,
+            x,
+            y,
+            grid: maze.grid,
+        }
+    }
+
+    /// Walls to knock through to join every tile, found with Kruskal over the tiles. A random
+    /// wall along the boundary is picked for each pair of tiles joined.
+    fn joins(&self, seed: u64) -> Vec<(usize, usize, Direction)> {
+        let mut rng = seeded_rng(Some(seed));
+        let mut sets = DisjointSet::new(self.count());
+        let mut boundaries = (0..self.count())
+            .flat_map(|tile| {
+                let (tx, ty) = (tile % self.across, tile / self.across);
+                let right = (tx + 1 < self.across).then_some((tile, tile + 1, Direction::Right));
+                let bottom =
+                    (ty + 1 < self.down).then_some((tile, tile + self.across, Direction::Bottom));
+
+                right.into_iter().chain(bottom)
+            })
+            .collect::<Vec<_>>();
+
+        boundaries.shuffle(&mut rng);
+
+        boundaries
+            .into_iter()
+            .filter(|(tile, neighbor, _)| sets.union(*tile, *neighbor))
+            .map(|(tile, _, dir)| {
+                let (x, y, width, height) = self.bounds(tile);
+
+                match dir {
+                    Direction::Right => (x + width - 1, y + rng.gen_range(0..height), dir),
+                    _ => (x + rng.gen_range(0..width), y + height - 1, dir),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A finished tile waiting to be copied into the grid
+struct Tile {
+    index: usize,
+    /// Top left cell of the tile in the grid
+    x: usize,
+    y: usize,
+    grid: Grid,
+}
+
+/// Every tile has already been generated, each call to `next` copies the next open wall of a tile
+/// into the grid and once they're all copied carves one of the walls joining the tiles. Dropping
+/// it leaves the tiles copied so far in place but not joined to each other.
+pub struct ParallelTiledSteps<'a, O> {
+    grid: &'a mut Grid,
+    observer: O,
+    /// Tiles left to copy, each is dropped once copied
+    tiles: VecDeque<Tile>,
+    /// Next wall of the front tile to check, the right & bottom walls of each cell in turn
+    slot: usize,
+    /// Walls joining the tiles left to carve, taken from the end
+    joins: Vec<(usize, usize, Direction)>,
+}
+
+impl<O: GenerationObserver<Direction>> ParallelTiledSteps<'_, O> {
+    fn carve(&mut self, x: usize, y: usize, dir: Direction) -> (usize, Direction) {
+        let cell = self.grid.cell_id(x, y);
+        let neighbor = match dir {
+            Direction::Right => cell + 1,
+            _ => cell + self.grid.cols,
+        };
+
+        self.grid.carve(cell, dir);
+        self.observer.carved(cell, dir, neighbor);
+
+        (cell, dir)
+    }
+}
+
+impl<O: GenerationObserver<Direction>> Iterator for ParallelTiledSteps<'_, O> {
+    type Item = (usize, Direction);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(tile) = self.tiles.front() {
+            let Some(id) = (self.slot / 2 < tile.grid.cell_count()).then_some(self.slot / 2) else {
+                self.tiles.pop_front();
+                self.slot = 0;
+                continue;
+            };
+
+            let dir = match self.slot % 2 {
+                0 => Direction::Right,
+                _ => Direction::Bottom,
+            };
+            let (x, y) = (id % tile.grid.cols, id / tile.grid.cols);
+            let (gx, gy) = (tile.x + x, tile.y + y);
+
+            self.slot += 1;
+
+            // The outside of a tile is always a wall, those are only opened by the joins
+            if !tile.grid.has_wall(x, y, dir) {
+                return Some(self.carve(gx, gy, dir));
+            }
+        }
+
+        let (x, y, dir) = self.joins.pop()?;
+
+        Some(self.carve(x, y, dir))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::builder::GraphBuilder;
+
+    #[test]
+    fn test_tiles_join_into_one_perfect_maze() {
+        let mut single = ParallelTiled::from_grid_size(23, 17).with_seed(7);
+        single.tile_size = 5;
+        single.threads = 1;
+        single.generate();
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&single.grid).build();
+        assert_eq!(23 * 17 - 1, graph.edge_count());
+        assert!(graph.is_perfect());
+        assert_eq!(Ok(()), single.grid.validate());
+
+        // The thread count doesn't change the maze, the seed does
+        let mut many = ParallelTiled::from_grid_size(23, 17).with_seed(7);
+        many.tile_size = 5;
+        many.threads = 4;
+        many.generate();
+
+        let mut other = ParallelTiled::from_grid_size(23, 17).with_seed(8);
+        other.tile_size = 5;
+        other.generate();
+
+        let walls = |maze: &ParallelTiled| maze.grid.cells().collect::<Vec<_>>();
+        assert!(walls(&single) == walls(&many));
+        assert!(walls(&single) != walls(&other));
+    }
+}
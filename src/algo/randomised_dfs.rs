@@ -1,62 +1,52 @@
-use rand::seq::SliceRandom;
-use std::collections::{HashSet, VecDeque};
+use rand::{rngs::StdRng, seq::SliceRandom};
+use std::collections::VecDeque;
 
 use super::grid::Grid;
-use super::maze::{Cell, Direction, MazeGenerate};
+use super::maze::{MazeGenerate, MazeGrid};
+use super::observer::GenerationObserver;
+use crate::util::{seeded_rng, BitSet};
 
 /// Based off this description and implementation description:
 /// [Wikipedia](https://en.wikipedia.org/wiki/Maze_generation_algorithm#Randomized_depth-first_search)
 /// Using this "Iterative implementation" with a Stack data structure
-pub struct RandomisedDFS {
-    pub grid: Grid,
+pub struct RandomisedDFS<G: MazeGrid = Grid> {
+    pub grid: G,
+    /// Seed for picking which unvisited neighbour to carve into next, `None` gives a different
+    /// maze every time
+    pub seed: Option<u64>,
 }
 
-impl RandomisedDFS {
+impl RandomisedDFS<Grid> {
     pub fn from_grid_size(rows: usize, cols: usize) -> Self {
         Self {
             grid: Grid::new(rows, cols),
-            ..RandomisedDFS::default()
+            seed: None,
         }
     }
+}
+
+impl<G: MazeGrid> RandomisedDFS<G> {
+    /// Generate into an existing grid, e.g. one with a wrapping `Topology` or a `Grid3D`
+    pub fn from_grid(grid: G) -> Self {
+        Self { grid, seed: None }
+    }
+
+    /// Make generation repeatable, the same grid & seed always give the same maze
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
 
     fn get_non_visited_neighbor_cells(
         &self,
-        cell: &Cell,
-        visited: &HashSet<(usize, usize)>,
-    ) -> Option<Vec<(Cell, Direction)>> {
-        let grid = &self.grid;
-
-        let cells = vec![
-            (
-                grid.get_neighbor_cell(&cell, Direction::Top),
-                Direction::Top,
-            ),
-            (
-                grid.get_neighbor_cell(&cell, Direction::Right),
-                Direction::Right,
-            ),
-            (
-                grid.get_neighbor_cell(&cell, Direction::Bottom),
-                Direction::Bottom,
-            ),
-            (
-                grid.get_neighbor_cell(&cell, Direction::Left),
-                Direction::Left,
-            ),
-        ];
-
-        let non_visited_cells = cells
+        cell: usize,
+        visited: &BitSet,
+    ) -> Option<Vec<(usize, G::Direction)>> {
+        let non_visited_cells = self
+            .grid
+            .neighbors(cell)
             .into_iter()
-            .filter_map(|item| {
-                if let Some(cell) = item.0 {
-                    Some((cell, item.1))
-                } else {
-                    None
-                }
-            })
-            .filter(|c| {
-                return !visited.contains(&c.0.to_visited_id());
-            })
+            .filter(|c| !visited.contains(c.0) && self.grid.preference(c.1) > 0.0)
             .collect::<Vec<_>>();
 
         match non_visited_cells.len() {
@@ -64,67 +54,127 @@ impl RandomisedDFS {
             _ => Some(non_visited_cells),
         }
     }
-
-    fn update_cell_walls(&mut self, target_cell: Cell, collapse_at: Direction) {
-        let updated_cell = &mut self.grid.matrix[target_cell.y][target_cell.x];
-
-        match collapse_at {
-            Direction::Top => {
-                updated_cell.walls[0] = false;
-            }
-            Direction::Right => {
-                updated_cell.walls[1] = false;
-            }
-            Direction::Bottom => {
-                updated_cell.walls[2] = false;
-            }
-            Direction::Left => {
-                updated_cell.walls[3] = false;
-            }
-        };
-    }
 }
 
-impl Default for RandomisedDFS {
+impl Default for RandomisedDFS<Grid> {
     fn default() -> Self {
         Self {
             grid: Grid::new(10, 10),
+            seed: None,
         }
     }
 }
 
-impl MazeGenerate for RandomisedDFS {
-    fn generate(&mut self) {
-        let mut stack = VecDeque::<Cell>::new();
-        let mut visited = HashSet::<(usize, usize)>::new();
-        let mut rng = rand::thread_rng();
+impl<G: MazeGrid> MazeGenerate for RandomisedDFS<G> {
+    type Direction = G::Direction;
+
+    fn generate_with(&mut self, observer: &mut dyn GenerationObserver<G::Direction>) {
+        self.steps_with(observer).for_each(drop);
+    }
+
+    fn steps(&mut self) -> impl Iterator<Item = (usize, G::Direction)> + '_ {
+        self.steps_with(())
+    }
+}
+
+impl<G: MazeGrid> RandomisedDFS<G> {
+    /// Step through generation, reporting each step to `observer` as it happens
+    pub fn steps_with<O: GenerationObserver<G::Direction>>(
+        &mut self,
+        mut observer: O,
+    ) -> RandomisedDFSSteps<'_, G, O> {
+        let rng = seeded_rng(self.seed);
+        let mut stack = VecDeque::<usize>::new();
+        let mut visited = BitSet::new(self.grid.cell_count());
 
         // Choose the initial cell, mark it as visited and push it to the stack
-        let start_cell = self.grid.matrix[0][0];
-        visited.insert(start_cell.to_visited_id());
-        stack.push_back(start_cell);
+        if self.grid.cell_count() > 0 {
+            let start_cell = 0;
+            visited.insert(start_cell);
+            stack.push_back(start_cell);
+            observer.visited(start_cell);
+            observer.frontier_changed(stack.len());
+        }
 
+        RandomisedDFSSteps {
+            maze: self,
+            observer,
+            stack,
+            visited,
+            rng,
+        }
+    }
+}
+
+/// Randomised DFS part way through. Each call to `next` backtracks down the stack until it finds
+/// a cell with an unvisited neighbour and carves into it. Dropping it leaves the cells visited so
+/// far joined in one passage and the rest of the grid uncarved.
+pub struct RandomisedDFSSteps<'a, G: MazeGrid, O> {
+    maze: &'a mut RandomisedDFS<G>,
+    observer: O,
+    stack: VecDeque<usize>,
+    visited: BitSet,
+    rng: StdRng,
+}
+
+impl<G: MazeGrid, O: GenerationObserver<G::Direction>> Iterator for RandomisedDFSSteps<'_, G, O> {
+    type Item = (usize, G::Direction);
+
+    fn next(&mut self) -> Option<Self::Item> {
         // While the stack is not empty
-        while !stack.is_empty() {
-            // While the stack is not empty
-            if let Some(cell) = stack.pop_back() {
-                // If the current cell has any neighbours which have not been visited
-                if let Some(found_neighbors) = self.get_non_visited_neighbor_cells(&cell, &visited)
-                {
-                    let neighbor = found_neighbors.choose(&mut rng).unwrap();
-
-                    // Remove the wall between the current cell and the chosen cell
-                    self.update_cell_walls(cell, neighbor.1);
-                    self.update_cell_walls(neighbor.0, neighbor.1.opposite());
-
-                    // Push the current cell to the stack
-                    stack.push_back(cell);
-
-                    // Mark the chosen cell as visited and push it to the stack
-                    visited.insert(neighbor.0.to_visited_id());
-                    stack.push_back(neighbor.0);
-                }
-            }
+        while let Some(cell) = self.stack.pop_back() {
+            // If the current cell has any neighbours which have not been visited
+            let Some(found_neighbors) = self
+                .maze
+                .get_non_visited_neighbor_cells(cell, &self.visited)
+            else {
+                self.observer.backtracked(cell);
+                self.observer.frontier_changed(self.stack.len());
+                continue;
+            };
+
+            let grid = &mut self.maze.grid;
+            let neighbor = *found_neighbors
+                .choose_weighted(&mut self.rng, |n| grid.preference(n.1))
+                .unwrap();
+
+            // Remove the wall between the current cell and the chosen cell
+            grid.carve(cell, neighbor.1);
+            self.observer.carved(cell, neighbor.1, neighbor.0);
+
+            // Push the current cell to the stack
+            self.stack.push_back(cell);
+
+            // Mark the chosen cell as visited and push it to the stack
+            self.visited.insert(neighbor.0);
+            self.stack.push_back(neighbor.0);
+            self.observer.visited(neighbor.0);
+            self.observer.frontier_changed(self.stack.len());
+
+            return Some((cell, neighbor.1));
         }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::builder::GraphBuilder;
+
+    #[test]
+    fn test_steps_can_pause_and_resume() {
+        let mut maze = RandomisedDFS::from_grid_size(5, 4);
+        let mut steps = maze.steps();
+
+        let first = steps.by_ref().take(5).count();
+        let rest = steps.count();
+
+        assert_eq!(5, first);
+        assert_eq!(14, rest);
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&maze.grid).build();
+        assert!(graph.bfs((0, 0), (3, 4)).is_some());
     }
 }
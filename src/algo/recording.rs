@@ -0,0 +1,49 @@
+use rand::RngCore;
+
+use super::maze::MazeGrid;
+use crate::util::DisjointSet;
+
+/// Wraps a grid and keeps a log of every wall a generator removes, in the order they're removed.
+///
+/// Passages a grid carves for itself in `MazeGrid::pre_carve` don't go through `carve` so they
+/// aren't logged.
+#[derive(Debug, Clone)]
+pub struct RecordedGrid<G: MazeGrid> {
+    pub grid: G,
+    /// (cell id, direction of the wall removed)
+    pub carves: Vec<(usize, G::Direction)>,
+}
+
+impl<G: MazeGrid> RecordedGrid<G> {
+    pub fn new(grid: G) -> Self {
+        Self {
+            grid,
+            carves: vec![],
+        }
+    }
+}
+
+impl<G: MazeGrid> MazeGrid for RecordedGrid<G> {
+    type Direction = G::Direction;
+
+    fn cell_count(&self) -> usize {
+        self.grid.cell_count()
+    }
+
+    fn neighbors(&self, id: usize) -> Vec<(usize, G::Direction)> {
+        self.grid.neighbors(id)
+    }
+
+    fn carve(&mut self, id: usize, dir: G::Direction) {
+        self.carves.push((id, dir));
+        self.grid.carve(id, dir);
+    }
+
+    fn preference(&self, dir: G::Direction) -> f64 {
+        self.grid.preference(dir)
+    }
+
+    fn pre_carve(&mut self, rng: &mut dyn RngCore, sets: &mut DisjointSet) {
+        self.grid.pre_carve(rng, sets);
+    }
+}
@@ -0,0 +1,397 @@
+use rand::{seq::SliceRandom, Rng, RngCore};
+
+use super::grid::Grid;
+use super::maze::{Cell, Direction, MazeGrid};
+use crate::graph::builder::GraphBuilder;
+use crate::util::DisjointSet;
+use image::{ImageBuffer, Luma};
+use std::convert::From;
+
+/// Colour of a passage where it tunnels under a bridge
+pub const UNDER_COLOUR: [u8; 1] = [170u8];
+/// Width & height in pixels of each cell in the rendered image
+const CELL_SIZE: u32 = 7;
+/// Space in pixels between the edge of a cell and the floor in the middle of it
+const INSET: u32 = 2;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum WeaveMove {
+    /// Step into the neighbouring cell
+    Over(Direction),
+    /// Tunnel under the neighbouring cell into the cell on the other side of it
+    Under(Direction),
+}
+
+/// A grid where passages can tunnel under a perpendicular corridor, as in a classic weave maze.
+///
+/// The walls of a cell describe the passage running over it, a cell with `under` set also has a
+/// passage running beneath it. The walls of the cells either side of the tunnel are open while
+/// the walls of the cell being tunnelled under stay closed.
+#[derive(Debug, Clone)]
+pub struct WeaveGrid {
+    pub grid: Grid,
+    /// Chance between 0 & 1 of generators creating a crossing when they have the option to
+    pub density: f64,
+}
+
+impl WeaveGrid {
+    pub fn new(rows: usize, cols: usize, density: f64) -> Self {
+        WeaveGrid::from_grid(Grid::new(rows, cols), density)
+    }
+
+    pub fn from_grid(grid: Grid, density: f64) -> Self {
+        Self { grid, density }
+    }
+
+    fn is_horizontal(dir: Direction) -> bool {
+        matches!(dir, Direction::Left | Direction::Right)
+    }
+
+    /// Whether a passage heading in `dir` can tunnel under `cell`, it must be a straight corridor
+    /// running across the direction of the tunnel
+    fn can_tunnel_under(cell: &Cell, dir: Direction) -> bool {
+        let [top, right, bottom, left] = cell.walls;
+
+        if cell.under {
+            return false;
+        }
+
+        if WeaveGrid::is_horizontal(dir) {
+            !top && !bottom && right && left
+        } else {
+            top && bottom && !right && !left
+        }
+    }
+
+    /// The cell a passage tunnelling from `cell` in `dir` would come out at
+    fn tunnel_exit(&self, cell: &Cell, dir: Direction) -> Option<Cell> {
+        let under = self.grid.get_neighbor_cell(cell, dir)?;
+
+        if !WeaveGrid::can_tunnel_under(&under, dir) {
+            return None;
+        }
+
+        self.grid
+            .get_neighbor_cell(&under, dir)
+            .filter(|exit| exit != cell)
+    }
+
+    /// The cell next to `cell` on `dir` if a passage could be carved over to it. The closed walls
+    /// either side of a crossing are where its tunnel comes through so can't be carved.
+    fn over_neighbor(&self, cell: &Cell, dir: Direction) -> Option<Cell> {
+        let tunnel_side = |c: &Cell, dir: Direction| c.under && c.walls[dir.index()];
+
+        self.grid
+            .get_neighbor_cell(cell, dir)
+            .filter(|n| !tunnel_side(cell, dir) && !tunnel_side(n, dir.opposite()))
+    }
+
+    /// Every cell joined to `cell` by an over or under passage
+    pub fn passages(&self, cell: &Cell) -> Vec<Cell> {
+        let mut cells = Vec::<Cell>::with_capacity(4);
+
+        for dir in Direction::ALL {
+            if let Some((x, y)) = self.grid.passage_to(cell.x, cell.y, dir) {
+                cells.push(self.grid.cell(x, y));
+            }
+        }
+
+        cells
+    }
+
+    pub fn generate_as_image(self) -> ImageBuffer<Luma<u8>, Vec<u8>> {
+        let image: ImageBuffer<Luma<u8>, Vec<u8>> = self.into();
+        image
+    }
+}
+
+impl MazeGrid for WeaveGrid {
+    type Direction = WeaveMove;
+
+    fn cell_count(&self) -> usize {
+        self.grid.cell_count()
+    }
+
+    fn neighbors(&self, id: usize) -> Vec<(usize, WeaveMove)> {
+        let cell = self.grid.cell_at(id);
+        let mut neighbors = Vec::<(usize, WeaveMove)>::with_capacity(8);
+
+        for dir in Direction::ALL {
+            if let Some(neighbor) = self.over_neighbor(&cell, dir) {
+                neighbors.push((
+                    self.grid.cell_id(neighbor.x, neighbor.y),
+                    WeaveMove::Over(dir),
+                ));
+            }
+
+            if let Some(exit) = self.tunnel_exit(&cell, dir) {
+                neighbors.push((self.grid.cell_id(exit.x, exit.y), WeaveMove::Under(dir)));
+            }
+        }
+
+        neighbors
+    }
+
+    fn carve(&mut self, id: usize, dir: WeaveMove) {
+        match dir {
+            WeaveMove::Over(dir) => self.grid.carve(id, dir),
+            WeaveMove::Under(dir) => {
+                let cell = self.grid.cell_at(id);
+
+                if self.tunnel_exit(&cell, dir).is_some() {
+                    let under = self.grid.get_neighbor_cell(&cell, dir).unwrap();
+
+                    // Marking the cell as a crossing opens the walls of the cells either side
+                    self.grid.set_under(under.x, under.y, Some(dir));
+                }
+            }
+        }
+    }
+
+    /// Carving a passage next to a cell can stop it being tunnelled under, and a tunnel rules out
+    /// passages into the sides of its crossing
+    fn can_carve(&self, id: usize, dir: WeaveMove) -> bool {
+        let cell = self.grid.cell_at(id);
+
+        match dir {
+            WeaveMove::Over(dir) => self.over_neighbor(&cell, dir).is_some(),
+            WeaveMove::Under(dir) => self.tunnel_exit(&cell, dir).is_some(),
+        }
+    }
+
+    fn preference(&self, dir: WeaveMove) -> f64 {
+        match dir {
+            WeaveMove::Over(_) => 1.0,
+            WeaveMove::Under(_) => self.density,
+        }
+    }
+
+    /// Scatter crossings over the grid before any other passages are carved, based off
+    /// [this approach](https://weblog.jamisbuck.org/2011/3/4/maze-generation-weave-mazes)
+    fn pre_carve(&mut self, rng: &mut dyn RngCore, sets: &mut DisjointSet) {
+        let mut cells = (0..self.cell_count()).collect::<Vec<_>>();
+        cells.shuffle(rng);
+
+        for id in cells {
+            if !rng.gen_bool(self.density.clamp(0.0, 1.0)) {
+                continue;
+            }
+
+            let cell = self.grid.cell_at(id);
+
+            if cell.under || cell.walls.iter().any(|wall| !wall) {
+                continue;
+            }
+
+            let around = Direction::ALL.map(|dir| self.grid.get_neighbor_cell(&cell, dir));
+
+            // Crossings need a cell on every side and can't sit next to another crossing
+            if around.iter().any(|c| c.is_none_or(|c| c.under)) {
+                continue;
+            }
+
+            let around = around.map(Option::unwrap);
+            let [top, right, bottom, left] = around;
+
+            // All five cells must be in separate sets or the crossing would create a loop
+            let ids = [cell, top, right, bottom, left].map(|c| self.grid.cell_id(c.x, c.y));
+            let separate = ids
+                .iter()
+                .enumerate()
+                .all(|(i, a)| ids.iter().skip(i + 1).all(|b| !sets.is_joined(*a, *b)));
+
+            if !separate {
+                continue;
+            }
+
+            let (over, under) = if rng.gen_bool(0.5) {
+                (
+                    [Direction::Top, Direction::Bottom],
+                    [Direction::Left, Direction::Right],
+                )
+            } else {
+                (
+                    [Direction::Left, Direction::Right],
+                    [Direction::Top, Direction::Bottom],
+                )
+            };
+
+            for dir in over {
+                self.grid.carve(id, dir);
+                sets.union(id, ids[dir.index() + 1]);
+            }
+
+            // Tunnel from the cell on one side to the cell on the other
+            self.grid.set_under(cell.x, cell.y, Some(under[0]));
+            sets.union(ids[under[0].index() + 1], ids[under[1].index() + 1]);
+        }
+    }
+}
+
+/// Cell level graph of the maze. A tunnel is an edge between the cells either side of it, the
+/// cell being tunnelled under isn't part of that edge so over & under stay distinct.
+impl From<&WeaveGrid> for GraphBuilder<(usize, usize)> {
+    fn from(weave: &WeaveGrid) -> Self {
+        let mut builder = GraphBuilder::<(usize, usize)>::new();
+
+        for cell in weave.grid.cells() {
+            builder.add_node(cell.to_visited_id());
+        }
+
+        for cell in weave.grid.cells() {
+            for neighbor in weave.passages(&cell) {
+                builder.add_edge(cell.to_visited_id(), neighbor.to_visited_id());
+            }
+        }
+
+        builder
+    }
+}
+
+/// Each cell is drawn as a `CELL_SIZE` square with the floor inset from the edges. A tunnel is
+/// drawn in `UNDER_COLOUR` and stops short of the corridor over it, leaving a gap either side of
+/// the bridge.
+impl From<WeaveGrid> for ImageBuffer<Luma<u8>, Vec<u8>> {
+    fn from(weave: WeaveGrid) -> Self {
+        let grid = &weave.grid;
+        let image_width = grid.cols as u32 * CELL_SIZE;
+        let image_height = grid.rows as u32 * CELL_SIZE;
+
+        let mut img: ImageBuffer<Luma<u8>, Vec<u8>> = ImageBuffer::new(image_width, image_height);
+
+        let mut fill = |x: std::ops::Range<u32>, y: std::ops::Range<u32>, colour: [u8; 1]| {
+            for px in x {
+                for py in y.clone() {
+                    img.get_pixel_mut(px, py).0 = colour;
+                }
+            }
+        };
+
+        let far = CELL_SIZE - INSET;
+
+        // The strip between the floor and the edge of the cell on `dir`, stopping `gap` pixels
+        // short of the floor. Offsets are relative to the top left of the cell.
+        let strip = |dir: Direction, gap: u32| match dir {
+            Direction::Top => (INSET..far, 0..INSET - gap),
+            Direction::Right => (far + gap..CELL_SIZE, INSET..far),
+            Direction::Bottom => (INSET..far, far + gap..CELL_SIZE),
+            Direction::Left => (0..INSET - gap, INSET..far),
+        };
+
+        for cell in grid.cells() {
+            let left = cell.x as u32 * CELL_SIZE;
+            let top = cell.y as u32 * CELL_SIZE;
+
+            fill(left + INSET..left + far, top + INSET..top + far, [255u8]);
+
+            for dir in Direction::ALL {
+                // The closed walls of a bridge are where the tunnel runs under it, the tunnel
+                // stops a pixel short of the bridge so the gap shows
+                let ((x, y), colour) = match (cell.walls[dir.index()], cell.under) {
+                    (false, _) => (strip(dir, 0), [255u8]),
+                    (true, true) => (strip(dir, 1), UNDER_COLOUR),
+                    (true, false) => continue,
+                };
+
+                fill(
+                    left + x.start..left + x.end,
+                    top + y.start..top + y.end,
+                    colour,
+                );
+            }
+        }
+
+        img
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::MazeGenerate, RandomisedDFS, RandomisedKruskal};
+    use crate::graph::{graph::walk_path, indexed::IndexedGraph};
+
+    fn assert_perfect(weave: &WeaveGrid) {
+        let graph = GraphBuilder::<(usize, usize)>::from(weave).build();
+        assert_eq!(weave.cell_count() - 1, graph.edge_count());
+
+        for cell in weave.grid.cells() {
+            assert!(graph.bfs((0, 0), cell.to_visited_id()).is_some());
+        }
+
+        assert!(graph.is_perfect());
+        // Every crossing is a straight corridor with its tunnel walls left standing
+        assert_eq!(Ok(()), weave.grid.validate());
+    }
+
+    #[test]
+    fn test_kruskal_weave_has_crossings() {
+        let mut maze = RandomisedKruskal::from_grid(WeaveGrid::new(10, 10, 1.0));
+        maze.generate();
+
+        assert!(maze.grid.grid.cells().any(|c| c.under));
+        assert_perfect(&maze.grid);
+    }
+
+    #[test]
+    fn test_crossings_keep_their_tunnel_walls() {
+        for seed in 0..10 {
+            let mut maze =
+                RandomisedKruskal::from_grid(WeaveGrid::new(20, 20, 0.5)).with_seed(seed);
+            maze.generate();
+
+            for cell in maze.grid.grid.cells().filter(|c| c.under) {
+                let [top, right, bottom, left] = cell.walls;
+
+                // The tunnel runs under one pair of closed walls, the bridge through the other
+                assert!(
+                    (top && bottom && !right && !left) || (!top && !bottom && right && left),
+                    "crossing at {:?} has walls {:?}",
+                    cell.to_visited_id(),
+                    cell.walls
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_routes_go_through_tunnels() {
+        // A corridor runs down the middle column over a tunnel along the middle row
+        let mut grid = Grid::new(3, 3);
+        grid.carve(grid.cell_id(1, 1), Direction::Top);
+        grid.carve(grid.cell_id(1, 1), Direction::Bottom);
+        grid.set_under(1, 1, Some(Direction::Left));
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&grid).build();
+        let path = graph.bfs((0, 1), (2, 1)).unwrap();
+        assert_eq!(Some(vec![(0, 1), (2, 1)]), walk_path(&path, &(2, 1)));
+        assert_eq!(
+            Some(vec![(1, 0), (1, 1), (1, 2)]),
+            walk_path(&graph.bfs((1, 0), (1, 2)).unwrap(), &(1, 2))
+        );
+        // Over & under stay apart, the bridge can't be reached from the tunnel
+        assert!(graph.bfs((0, 1), (1, 1)).is_none());
+
+        let indexed = IndexedGraph::from(&grid);
+        assert_eq!(Some(vec![3, 5]), indexed.bfs(3, 5));
+        assert_eq!(None, indexed.bfs(3, 4));
+
+        let weave = WeaveGrid::from_grid(grid, 0.0);
+        assert_eq!(
+            vec![(2, 1)],
+            weave
+                .passages(&weave.grid.cell(0, 1))
+                .iter()
+                .map(|c| c.to_visited_id())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_dfs_weave_is_perfect() {
+        let mut maze = RandomisedDFS::from_grid(WeaveGrid::new(12, 9, 1.0));
+        maze.generate();
+
+        assert_perfect(&maze.grid);
+    }
+}
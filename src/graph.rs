@@ -1,2 +1,7 @@
 pub mod builder;
+pub mod connectivity;
+pub mod export;
+#[allow(clippy::module_inception)]
 pub mod graph;
+pub mod indexed;
+pub mod metrics;
@@ -1,22 +1,29 @@
 use std::{
-    cell::RefCell,
     cmp::{Eq, PartialEq},
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fmt::Debug,
     hash::Hash,
 };
 
-use super::graph::{Graph, Node};
+use super::graph::Graph;
 
+/// Graph under construction, each node maps to its neighbours and the weight of the edge to them.
+/// Edges go both ways unless they're added with `add_directed_edge`. Edges added without a weight
+/// have a weight of 1.
 #[derive(Debug)]
 pub struct GraphBuilder<T: PartialEq + Eq + Hash + Clone + Debug + Ord> {
-    pub vertices: HashMap<T, HashSet<T>>,
+    pub vertices: HashMap<T, HashMap<T, usize>>,
+    /// Second edges between nodes that were already joined, e.g. two passages between the same
+    /// cells on a narrow cylinder. Only one edge is kept for each pair but `find_cycle` counts
+    /// each of these as a loop.
+    pub parallel: Vec<(T, T)>,
 }
 
 impl<T: PartialEq + Eq + Hash + Clone + Debug + Ord> Default for GraphBuilder<T> {
     fn default() -> Self {
         GraphBuilder {
             vertices: HashMap::new(),
+            parallel: Vec::new(),
         }
     }
 }
@@ -28,7 +35,7 @@ impl<T: PartialEq + Eq + Hash + Clone + Debug + Ord> GraphBuilder<T> {
 
     fn _insert_node(builder: &mut GraphBuilder<T>, node: &T) {
         if !builder.vertices.contains_key(node) {
-            builder.vertices.insert(node.clone(), HashSet::new());
+            builder.vertices.insert(node.clone(), HashMap::new());
         }
     }
 
@@ -41,31 +48,72 @@ impl<T: PartialEq + Eq + Hash + Clone + Debug + Ord> GraphBuilder<T> {
         GraphBuilder::<T>::_insert_node(self, &node);
     }
 
-    fn _insert_edge(builder: &mut GraphBuilder<T>, from: &T, to: &T) {
-        let has_from = builder.vertices.contains_key(&from);
-        let has_to = builder.vertices.contains_key(&to);
+    fn _insert_edge(builder: &mut GraphBuilder<T>, from: &T, to: &T, weight: usize) {
+        let has_from = builder.vertices.contains_key(from);
+        let has_to = builder.vertices.contains_key(to);
 
         if !has_from || !has_to {
             return;
         }
 
-        if let Some(from_vert) = builder.vertices.get_mut(&from) {
-            from_vert.insert(to.clone());
+        if let Some(from_vert) = builder.vertices.get_mut(from) {
+            from_vert.insert(to.clone(), weight);
         }
 
-        if let Some(to_vert) = builder.vertices.get_mut(&to) {
-            to_vert.insert(from.clone());
+        if let Some(to_vert) = builder.vertices.get_mut(to) {
+            to_vert.insert(from.clone(), weight);
+        }
+    }
+
+    fn _insert_directed_edge(builder: &mut GraphBuilder<T>, from: &T, to: &T, weight: usize) {
+        if !builder.vertices.contains_key(to) {
+            return;
+        }
+
+        if let Some(from_vert) = builder.vertices.get_mut(from) {
+            from_vert.insert(to.clone(), weight);
         }
     }
 
     pub fn insert_edge(mut self, from: T, to: T) -> GraphBuilder<T> {
-        GraphBuilder::<T>::_insert_edge(&mut self, &from, &to);
+        GraphBuilder::<T>::_insert_edge(&mut self, &from, &to, 1);
 
         self
     }
 
     pub fn add_edge(&mut self, from: T, to: T) {
-        GraphBuilder::<T>::_insert_edge(self, &from, &to);
+        GraphBuilder::<T>::_insert_edge(self, &from, &to, 1);
+    }
+
+    pub fn insert_weighted_edge(mut self, from: T, to: T, weight: usize) -> GraphBuilder<T> {
+        GraphBuilder::<T>::_insert_edge(&mut self, &from, &to, weight);
+
+        self
+    }
+
+    /// Add an edge with a cost of `weight` to travel along it, replacing the weight of an
+    /// existing edge
+    pub fn add_weighted_edge(&mut self, from: T, to: T, weight: usize) {
+        GraphBuilder::<T>::_insert_edge(self, &from, &to, weight);
+    }
+
+    pub fn insert_directed_edge(mut self, from: T, to: T, weight: usize) -> GraphBuilder<T> {
+        GraphBuilder::<T>::_insert_directed_edge(&mut self, &from, &to, weight);
+
+        self
+    }
+
+    /// Add an edge that can only be travelled from `from` to `to`. Adding the reverse edge as
+    /// well, with either method, makes it two way again.
+    pub fn add_directed_edge(&mut self, from: T, to: T, weight: usize) {
+        GraphBuilder::<T>::_insert_directed_edge(self, &from, &to, weight);
+    }
+
+    /// Whether there is an edge between `from` & `to` going either way
+    pub fn has_edge(&self, from: &T, to: &T) -> bool {
+        let joined = |a: &T, b: &T| self.vertices.get(a).is_some_and(|n| n.contains_key(b));
+
+        joined(from, to) || joined(to, from)
     }
 
     pub fn from_nodes(nodes: Vec<T>) -> GraphBuilder<T> {
@@ -84,16 +132,26 @@ impl<T: PartialEq + Eq + Hash + Clone + Debug + Ord> GraphBuilder<T> {
         for (from, to) in edges {
             GraphBuilder::<T>::_insert_node(&mut graph, &from);
             GraphBuilder::<T>::_insert_node(&mut graph, &to);
-            GraphBuilder::<T>::_insert_edge(&mut graph, &from, &to);
+            GraphBuilder::<T>::_insert_edge(&mut graph, &from, &to, 1);
         }
 
         graph
     }
 
-    pub fn build(self) -> Graph<T> {
-        Graph {
-            vertices: self.vertices,
-            path: RefCell::new(HashMap::<T, Node<T>>::new()),
+    pub fn from_weighted_edges(edges: Vec<(T, T, usize)>) -> GraphBuilder<T> {
+        let mut graph = GraphBuilder::<T>::new();
+
+        for (from, to, weight) in edges {
+            GraphBuilder::<T>::_insert_node(&mut graph, &from);
+            GraphBuilder::<T>::_insert_node(&mut graph, &to);
+            GraphBuilder::<T>::_insert_edge(&mut graph, &from, &to, weight);
         }
+
+        graph
+    }
+
+    /// Freeze the graph into compressed sparse row form for searching
+    pub fn build(self) -> Graph<T> {
+        Graph::from_adjacency(self.vertices, self.parallel)
     }
 }
@@ -0,0 +1,183 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+};
+
+use super::graph::Graph;
+
+impl<T> Graph<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord,
+{
+    /// Groups of nodes that can reach each other, each group is sorted and the groups are in
+    /// order of their lowest node. One way edges count as going both ways, so in a directed graph
+    /// these are the weakly connected components.
+    pub fn connected_components(&self) -> Vec<Vec<T>> {
+        let mut seen = HashSet::<T>::new();
+        let mut components = Vec::<Vec<T>>::new();
+
+        for node in self.nodes().iter().cloned() {
+            if seen.contains(&node) {
+                continue;
+            }
+
+            let mut component = match self.is_directed() {
+                true => self.weak_component(node),
+                false => self.distances(node).into_keys().collect::<Vec<_>>(),
+            };
+            component.sort();
+            seen.extend(component.iter().cloned());
+            components.push(component);
+        }
+
+        components
+    }
+
+    /// Nodes joined to `start` by edges in either direction
+    fn weak_component(&self, start: T) -> Vec<T> {
+        let mut seen = HashSet::from([start.clone()]);
+        let mut stack = vec![start];
+
+        while let Some(node) = stack.pop() {
+            for next in self.sorted_neighbours(&node) {
+                if seen.insert(next.clone()) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        seen.into_iter().collect()
+    }
+
+    /// A loop in the graph as the nodes around it in order, `None` if there aren't any loops.
+    /// Edges are followed either way, so a one way edge and its reverse aren't a loop. Two nodes
+    /// joined by parallel edges are a loop of their own, going out along one and back along the
+    /// other.
+    pub fn find_cycle(&self) -> Option<Vec<T>> {
+        let mut parents = HashMap::<T, Option<T>>::new();
+
+        for root in self.nodes() {
+            if parents.contains_key(root) {
+                continue;
+            }
+
+            parents.insert(root.clone(), None);
+            let mut stack = vec![(root.clone(), self.sorted_neighbours(root))];
+
+            while let Some((node, neighbours)) = stack.last_mut() {
+                let Some(next) = neighbours.pop() else {
+                    stack.pop();
+                    continue;
+                };
+
+                let node = node.clone();
+
+                if parents[&node].as_ref() == Some(&next) {
+                    continue;
+                }
+
+                // Any other edge back to a node already seen leads to an ancestor on the stack
+                if parents.contains_key(&next) {
+                    let mut cycle = vec![node];
+
+                    while cycle.last() != Some(&next) {
+                        let parent = parents[cycle.last()?].clone()?;
+                        cycle.push(parent);
+                    }
+
+                    cycle.reverse();
+                    return Some(cycle);
+                }
+
+                parents.insert(next.clone(), Some(node));
+                stack.push((next.clone(), self.sorted_neighbours(&next)));
+            }
+        }
+
+        self.parallel_edges()
+            .first()
+            .map(|(from, to)| vec![from.clone(), to.clone()])
+    }
+
+    /// Nodes joined to `node` by an edge in either direction
+    fn sorted_neighbours(&self, node: &T) -> Vec<T> {
+        let mut neighbours = self
+            .neighbours(node)
+            .chain(self.incoming(node))
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<_>>();
+
+        // Popped off the end, so the lowest is visited first
+        neighbours.sort_by(|a, b| b.cmp(a));
+        neighbours.dedup();
+        neighbours
+    }
+
+    pub fn has_cycle(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+
+    /// Every node can reach every other by exactly one route, i.e. the graph is a tree
+    pub fn is_perfect(&self) -> bool {
+        self.connected_components().len() == 1 && !self.has_cycle()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::algo::{
+        grid::{Grid, Topology},
+        maze::{Direction, MazeGenerate},
+        RandomisedKruskal,
+    };
+    use crate::graph::builder::GraphBuilder;
+
+    #[test]
+    fn test_components_and_cycles() {
+        let graph = GraphBuilder::from_edges(vec![(1, 2), (2, 3), (3, 4), (4, 2), (5, 6)])
+            .insert_node(7)
+            .build();
+
+        assert_eq!(
+            vec![vec![1, 2, 3, 4], vec![5, 6], vec![7]],
+            graph.connected_components()
+        );
+        assert_eq!(Some(vec![2, 3, 4]), graph.find_cycle());
+        assert!(!graph.is_perfect());
+
+        let mut maze = RandomisedKruskal::from_grid_size(10, 10);
+        maze.generate();
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&maze.grid).build();
+        assert!(graph.is_perfect());
+
+        let mut grid: Grid = maze.grid.clone();
+        grid.braid(1.0);
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&grid).build();
+        assert_eq!(1, graph.connected_components().len());
+        assert!(graph.has_cycle());
+    }
+
+    #[test]
+    fn test_parallel_passages_are_a_cycle() {
+        let mut grid = Grid::with_topology(2, 2, Topology::Cylinder);
+        grid.set_wall(0, 0, Direction::Right, false);
+        grid.set_wall(0, 0, Direction::Bottom, false);
+        grid.set_wall(1, 0, Direction::Bottom, false);
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&grid).build();
+        assert!(graph.is_perfect());
+
+        // The passage across the seam joins the same two cells a second time
+        grid.set_wall(1, 0, Direction::Right, false);
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&grid).build();
+        assert_eq!(3, graph.edge_count());
+        assert_eq!(&[((1, 0), (0, 0))], graph.parallel_edges());
+        assert_eq!(Some(vec![(1, 0), (0, 0)]), graph.find_cycle());
+        assert!(!graph.is_perfect());
+        assert!(!graph.metrics().is_tree);
+    }
+}
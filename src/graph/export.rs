@@ -0,0 +1,548 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Debug, Write},
+    hash::Hash,
+};
+
+use serde_json::Value;
+
+use super::builder::GraphBuilder;
+use super::graph::Graph;
+
+/// Nodes that can be written out by the graph exporters and read back by
+/// `GraphBuilder::from_json`
+pub trait ExportNode: Sized {
+    /// Name of the node in DOT & GraphML, different for every node
+    fn label(&self) -> String;
+
+    /// Where to draw the node, e.g. the column & row of a cell
+    fn position(&self) -> Option<(f64, f64)> {
+        None
+    }
+
+    /// The node as a JSON value
+    fn to_json(&self) -> Value;
+
+    fn from_json(value: &Value) -> Option<Self>;
+}
+
+macro_rules! export_number {
+    ($($t:ty),*) => {
+        $(
+            impl ExportNode for $t {
+                fn label(&self) -> String {
+                    self.to_string()
+                }
+
+                fn to_json(&self) -> Value {
+                    Value::from(*self)
+                }
+
+                fn from_json(value: &Value) -> Option<Self> {
+                    value.as_u64().and_then(|n| n.try_into().ok())
+                }
+            }
+        )*
+    };
+}
+
+export_number!(usize, u32);
+
+impl ExportNode for char {
+    fn label(&self) -> String {
+        self.to_string()
+    }
+
+    fn to_json(&self) -> Value {
+        Value::from(self.to_string())
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        let mut chars = value.as_str()?.chars();
+
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    }
+}
+
+/// Points are a column & row, e.g. a cell of a `Grid` or a pixel of the maze image
+macro_rules! export_point {
+    ($($t:ty),*) => {
+        $(
+            impl ExportNode for ($t, $t) {
+                fn label(&self) -> String {
+                    format!("{},{}", self.0, self.1)
+                }
+
+                fn position(&self) -> Option<(f64, f64)> {
+                    Some((self.0 as f64, self.1 as f64))
+                }
+
+                fn to_json(&self) -> Value {
+                    Value::from(vec![self.0, self.1])
+                }
+
+                fn from_json(value: &Value) -> Option<Self> {
+                    match value.as_array()?.as_slice() {
+                        [x, y] => Some((<$t>::from_json(x)?, <$t>::from_json(y)?)),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+export_point!(usize, u32);
+
+/// Cells of a `Grid3D` as column, row & level, without a position as the levels overlap
+impl ExportNode for (usize, usize, usize) {
+    fn label(&self) -> String {
+        format!("{},{},{}", self.0, self.1, self.2)
+    }
+
+    fn to_json(&self) -> Value {
+        Value::from(vec![self.0, self.1, self.2])
+    }
+
+    fn from_json(value: &Value) -> Option<Self> {
+        match value.as_array()?.as_slice() {
+            [x, y, z] => Some((
+                usize::from_json(x)?,
+                usize::from_json(y)?,
+                usize::from_json(z)?,
+            )),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportOptions<T> {
+    /// Name of the graph in DOT & GraphML
+    pub name: String,
+    /// Route through the graph, e.g. a solution from `walk_path`. Edges between nodes next to
+    /// each other in it are highlighted.
+    pub solution: Option<Vec<T>>,
+    /// Pin each node at its `ExportNode::position` in DOT output, so `neato` draws the graph in
+    /// the shape of the maze
+    pub positions: bool,
+}
+
+impl<T> Default for ExportOptions<T> {
+    fn default() -> Self {
+        Self {
+            name: "maze".to_string(),
+            solution: None,
+            positions: false,
+        }
+    }
+}
+
+/// Something wrong with a JSON edge list passed to `GraphBuilder::from_json`
+#[derive(Debug)]
+pub enum ImportError {
+    /// The text isn't valid JSON, or nests deeper than `serde_json` allows
+    Json(serde_json::Error),
+    /// The document parsed but isn't an edge list, or a node in it can't be read
+    Format(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Json(err) => write!(f, "invalid JSON: {}", err),
+            ImportError::Format(message) => write!(f, "invalid edge list: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<serde_json::Error> for ImportError {
+    fn from(err: serde_json::Error) -> Self {
+        ImportError::Json(err)
+    }
+}
+
+/// Escape `text` for a double quoted DOT id
+fn dot_id(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl<T> Graph<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord + ExportNode,
+{
+    /// Every edge as (from, to, weight) node indexes, in order of index. Two way edges are only
+    /// written once unless the graph is directed, then each direction is its own edge.
+    fn export_edges(&self) -> Vec<(usize, usize, usize)> {
+        let directed = self.is_directed();
+
+        (0..self.len())
+            .flat_map(|from| {
+                self.edges_at(from)
+                    .filter(move |(to, _)| directed || *to > from)
+                    .map(move |(to, weight)| (from, to, weight))
+            })
+            .collect()
+    }
+
+    /// Indexes of the edges along the solution, lowest index first unless the graph is directed
+    fn solution_edges(&self, options: &ExportOptions<T>) -> HashSet<(usize, usize)> {
+        let Some(solution) = &options.solution else {
+            return HashSet::new();
+        };
+
+        solution
+            .windows(2)
+            .filter_map(|step| Some((self.index_of(&step[0])?, self.index_of(&step[1])?)))
+            .map(|(a, b)| match self.is_directed() {
+                true => (a, b),
+                false => (a.min(b), a.max(b)),
+            })
+            .collect()
+    }
+
+    /// Graphviz DOT, with the weight of each edge as its label and the solution in red. A
+    /// directed graph is written as a `digraph`.
+    pub fn to_dot(&self, options: &ExportOptions<T>) -> String {
+        let solution = self.solution_edges(options);
+        let (kind, edge) = match self.is_directed() {
+            true => ("digraph", "->"),
+            false => ("graph", "--"),
+        };
+        let mut dot = String::new();
+
+        let _ = writeln!(dot, "{} {} {{", kind, dot_id(&options.name));
+        let _ = writeln!(dot, "  node [shape=circle, fontsize=8];");
+
+        for node in self.nodes() {
+            let _ = write!(dot, "  {}", dot_id(&node.label()));
+
+            // Graphviz puts y up, flip it so the maze isn't drawn upside down. Subtracting from 0
+            // rather than negating keeps the top row at 0 instead of -0
+            match node.position().filter(|_| options.positions) {
+                Some((x, y)) => {
+                    let _ = writeln!(dot, " [pos=\"{},{}!\"];", x, 0.0 - y);
+                }
+                None => dot.push_str(";\n"),
+            }
+        }
+
+        for (from, to, weight) in self.export_edges() {
+            let _ = write!(
+                dot,
+                "  {} {} {} [label=\"{}\"",
+                dot_id(&self.nodes()[from].label()),
+                edge,
+                dot_id(&self.nodes()[to].label()),
+                weight
+            );
+
+            if solution.contains(&(from, to)) {
+                dot.push_str(", color=red, penwidth=3");
+            }
+
+            dot.push_str("];\n");
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// GraphML, nodes have `x` & `y` data where they have a position and edges have their
+    /// `weight` & whether they're on the `solution`
+    pub fn to_graphml(&self, options: &ExportOptions<T>) -> String {
+        let solution = self.solution_edges(options);
+        let mut xml = String::new();
+
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        xml.push_str("  <key id=\"x\" for=\"node\" attr.name=\"x\" attr.type=\"double\"/>\n");
+        xml.push_str("  <key id=\"y\" for=\"node\" attr.name=\"y\" attr.type=\"double\"/>\n");
+        xml.push_str(
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>\n",
+        );
+        xml.push_str(
+            "  <key id=\"solution\" for=\"edge\" attr.name=\"solution\" attr.type=\"boolean\">\n",
+        );
+        xml.push_str("    <default>false</default>\n  </key>\n");
+        let _ = writeln!(
+            xml,
+            "  <graph id=\"{}\" edgedefault=\"{}\">",
+            xml_escape(&options.name),
+            match self.is_directed() {
+                true => "directed",
+                false => "undirected",
+            }
+        );
+
+        for node in self.nodes() {
+            let id = xml_escape(&node.label());
+
+            match node.position() {
+                Some((x, y)) => {
+                    let _ = writeln!(
+                        xml,
+                        "    <node id=\"{}\"><data key=\"x\">{}</data><data key=\"y\">{}</data></node>",
+                        id, x, y
+                    );
+                }
+                None => {
+                    let _ = writeln!(xml, "    <node id=\"{}\"/>", id);
+                }
+            }
+        }
+
+        for (from, to, weight) in self.export_edges() {
+            let _ = write!(
+                xml,
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"weight\">{}</data>",
+                xml_escape(&self.nodes()[from].label()),
+                xml_escape(&self.nodes()[to].label()),
+                weight
+            );
+
+            if solution.contains(&(from, to)) {
+                xml.push_str("<data key=\"solution\">true</data>");
+            }
+
+            xml.push_str("</edge>\n");
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+
+    /// JSON edge list, every node and every edge with its weight & whether it's on the solution.
+    /// `GraphBuilder::from_json` reads it back.
+    pub fn to_json(&self, options: &ExportOptions<T>) -> String {
+        let solution = self.solution_edges(options);
+        let nodes = self
+            .nodes()
+            .iter()
+            .map(|node| format!("    {}", node.to_json()))
+            .collect::<Vec<_>>();
+        let edges = self
+            .export_edges()
+            .into_iter()
+            .map(|(from, to, weight)| {
+                format!(
+                    "    {{\"from\": {}, \"to\": {}, \"weight\": {}, \"solution\": {}}}",
+                    self.nodes()[from].to_json(),
+                    self.nodes()[to].to_json(),
+                    weight,
+                    solution.contains(&(from, to))
+                )
+            })
+            .collect::<Vec<_>>();
+
+        format!(
+            "{{\n  \"name\": {},\n  \"directed\": {},\n  \"nodes\": [\n{}\n  ],\n  \"edges\": [\n{}\n  ]\n}}\n",
+            Value::from(options.name.as_str()),
+            self.is_directed(),
+            nodes.join(",\n"),
+            edges.join(",\n")
+        )
+    }
+}
+
+impl<T> GraphBuilder<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord + ExportNode,
+{
+    /// Read a JSON edge list like the ones `Graph::to_json` writes. `nodes` is optional, the
+    /// ends of every edge are added anyway, and edges without a `weight` get a weight of 1. Edges
+    /// only go one way when `directed` is true.
+    pub fn from_json(text: &str) -> Result<GraphBuilder<T>, ImportError> {
+        let document = serde_json::from_str::<Value>(text)?;
+        let node = |value: &Value| {
+            T::from_json(value).ok_or_else(|| ImportError::Format(format!("bad node {}", value)))
+        };
+
+        let directed = match document.get("directed") {
+            Some(directed) => directed
+                .as_bool()
+                .ok_or_else(|| ImportError::Format("directed isn't true or false".to_string()))?,
+            None => false,
+        };
+        let mut builder = GraphBuilder::<T>::new();
+
+        if let Some(nodes) = document.get("nodes") {
+            let nodes = nodes
+                .as_array()
+                .ok_or_else(|| ImportError::Format("nodes isn't a list".to_string()))?;
+
+            for value in nodes {
+                builder.add_node(node(value)?);
+            }
+        }
+
+        let edges = document
+            .get("edges")
+            .and_then(|edges| edges.as_array())
+            .ok_or_else(|| ImportError::Format("missing a list of edges".to_string()))?;
+
+        for edge in edges {
+            let (Some(from), Some(to)) = (edge.get("from"), edge.get("to")) else {
+                return Err(ImportError::Format(format!(
+                    "edge without a from & to {}",
+                    edge
+                )));
+            };
+
+            let weight = match edge.get("weight") {
+                Some(weight) => weight
+                    .as_u64()
+                    .and_then(|weight| weight.try_into().ok())
+                    .ok_or_else(|| ImportError::Format(format!("bad weight {}", weight)))?,
+                None => 1,
+            };
+            let (from, to) = (node(from)?, node(to)?);
+
+            builder.add_node(from.clone());
+            builder.add_node(to.clone());
+            match directed {
+                true => builder.add_directed_edge(from, to, weight),
+                false => builder.add_weighted_edge(from, to, weight),
+            }
+        }
+
+        Ok(builder)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+    use crate::graph::graph::walk_path;
+
+    #[test]
+    fn test_json_round_trip_and_exports() {
+        let mut maze = RandomisedDFS::from_grid_size(6, 6).with_seed(5);
+        maze.generate();
+        maze.grid.braid(0.5);
+
+        let graph = GraphBuilder::<(usize, usize)>::from(&maze.grid).build();
+        let route = walk_path(&graph.bfs((0, 0), (5, 5)).unwrap(), &(5, 5)).unwrap();
+        let options = ExportOptions {
+            solution: Some(route.clone()),
+            positions: true,
+            ..ExportOptions::default()
+        };
+
+        let json = graph.to_json(&options);
+        let read = GraphBuilder::<(usize, usize)>::from_json(&json)
+            .unwrap()
+            .build();
+
+        assert_eq!(graph.nodes(), read.nodes());
+        assert_eq!(graph.edge_count(), read.edge_count());
+        assert!((0..graph.len()).all(|i| graph.edges_at(i).eq(read.edges_at(i))));
+        assert_eq!(route.len() - 1, json.matches("\"solution\": true").count());
+
+        let dot = graph.to_dot(&options);
+        assert_eq!(graph.edge_count(), dot.matches(" -- ").count());
+        assert_eq!(route.len() - 1, dot.matches("color=red").count());
+        assert!(dot.contains("\"5,5\" [pos=\"5,-5!\"];"));
+
+        let graphml = graph.to_graphml(&options);
+        assert_eq!(graph.edge_count(), graphml.matches("<edge ").count());
+        assert_eq!(route.len() - 1, graphml.matches(">true<").count());
+
+        // Weights default to 1 and nodes are only needed for ones without edges
+        let read = GraphBuilder::<char>::from_json(
+            r#"{"nodes": ["d"], "edges": [{"from": "a", "to": "b", "weight": 3}, {"from": "b", "to": "c"}]}"#,
+        )
+        .unwrap()
+        .build();
+
+        assert_eq!(&['a', 'b', 'c', 'd'], read.nodes());
+        assert_eq!(
+            (Some(3), Some(1)),
+            (read.weight(&'a', &'b'), read.weight(&'c', &'b'))
+        );
+
+        // Large weights aren't rounded on the way in
+        let read = GraphBuilder::<char>::from_json(
+            r#"{"edges": [{"from": "a", "to": "b", "weight": 9007199254740993}]}"#,
+        )
+        .unwrap()
+        .build();
+        assert_eq!(Some(9_007_199_254_740_993), read.weight(&'a', &'b'));
+
+        // Each direction of a directed graph is its own edge
+        let json = r#"{"directed": true, "edges": [{"from": "a", "to": "b"}, {"from": "b", "to": "c"}, {"from": "c", "to": "b", "weight": 2}]}"#;
+        let directed = GraphBuilder::<char>::from_json(json).unwrap().build();
+
+        assert!(directed.is_directed());
+        assert_eq!(
+            (Some(1), None),
+            (directed.weight(&'a', &'b'), directed.weight(&'b', &'a'))
+        );
+        assert_eq!(2, directed.edge_count());
+
+        let options = ExportOptions {
+            solution: Some(vec!['a', 'b', 'c']),
+            ..ExportOptions::default()
+        };
+        let dot = directed.to_dot(&options);
+
+        assert!(dot.starts_with("digraph"));
+        assert_eq!(3, dot.matches(" -> ").count());
+        assert!(dot.contains("\"b\" -> \"c\" [label=\"1\", color=red"));
+        assert!(dot.contains("\"c\" -> \"b\" [label=\"2\"];"));
+        assert!(directed
+            .to_graphml(&options)
+            .contains("edgedefault=\"directed\""));
+
+        let read = GraphBuilder::<char>::from_json(&directed.to_json(&options))
+            .unwrap()
+            .build();
+        assert!((0..read.len()).all(|i| directed.edges_at(i).eq(read.edges_at(i))));
+
+        assert!(matches!(
+            GraphBuilder::<char>::from_json(r#"{"edges": [{"from": 1, "to": "b"}]}"#),
+            Err(ImportError::Format(_))
+        ));
+        assert!(matches!(
+            GraphBuilder::<char>::from_json("{\"edges\": ["),
+            Err(ImportError::Json(_))
+        ));
+        assert!(matches!(
+            GraphBuilder::<usize>::from_json(&"[".repeat(200_000)),
+            Err(ImportError::Json(_))
+        ));
+
+        // Numbers have to follow the JSON grammar, and weights can't be negative
+        for weight in ["01", "+1", "1.", ".5"] {
+            let json = format!(
+                r#"{{"edges": [{{"from": 1, "to": 2, "weight": {}}}]}}"#,
+                weight
+            );
+
+            assert!(matches!(
+                GraphBuilder::<usize>::from_json(&json),
+                Err(ImportError::Json(_))
+            ));
+        }
+
+        let negative =
+            GraphBuilder::<usize>::from_json(r#"{"edges": [{"from": 1, "to": 2, "weight": -1}]}"#);
+        assert_eq!(
+            "invalid edge list: bad weight -1",
+            negative.unwrap_err().to_string()
+        );
+    }
+}
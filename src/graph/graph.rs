@@ -1,17 +1,167 @@
 use std::{
     cell::{Ref, RefCell},
+    cmp::Reverse,
     cmp::{Eq, PartialEq},
-    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    collections::{BTreeSet, BinaryHeap, HashMap, HashSet, VecDeque},
     fmt::Debug,
     hash::Hash,
 };
 
-type Path<T> = HashMap<T, Node<T>>;
+use crate::util::{route_to, BitSet, NO_PARENT};
 
+pub type Path<T> = HashMap<T, Node<T>>;
+
+/// Edges of every node in compressed sparse row form. The edges of the node at index `i` go to
+/// `targets[offsets[i]..offsets[i + 1]]`, with the weight of each edge in the same place in
+/// `weights`.
+#[derive(Debug)]
+struct Csr {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    weights: Vec<usize>,
+}
+
+impl Csr {
+    /// Lay out `edges`, the (target, weight) pairs of each node, sorting each node's edges
+    fn new(edges: Vec<Vec<(usize, usize)>>) -> Self {
+        let mut offsets = Vec::<usize>::with_capacity(edges.len() + 1);
+        let mut targets = Vec::<usize>::new();
+        let mut weights = Vec::<usize>::new();
+
+        offsets.push(0);
+
+        for mut node in edges {
+            node.sort();
+
+            for (to, weight) in node {
+                targets.push(to);
+                weights.push(weight);
+            }
+
+            offsets.push(targets.len());
+        }
+
+        Self {
+            offsets,
+            targets,
+            weights,
+        }
+    }
+
+    fn edges(&self, index: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let range = self.offsets[index]..self.offsets[index + 1];
+
+        self.targets[range.clone()]
+            .iter()
+            .copied()
+            .zip(self.weights[range].iter().copied())
+    }
+}
+
+/// Graph frozen in compressed sparse row form, built with `GraphBuilder::build`. Each node has
+/// an index, its position in `nodes`, and searches work on the indexes and only map back to `T`
+/// for their results. Edges go both ways unless they were added with
+/// `GraphBuilder::add_directed_edge`, in which case the edges into each node are also kept for
+/// searches that work backwards from the end.
 #[derive(Debug)]
 pub struct Graph<T: PartialEq + Eq + Hash + Clone + Debug + Ord> {
-    pub vertices: HashMap<T, HashSet<T>>,
+    /// Every node sorted, so the layout doesn't depend on hash order
+    nodes: Vec<T>,
+    /// Index of each node in `nodes`
+    indexes: HashMap<T, usize>,
+    /// Edges out of each node
+    forward: Csr,
+    /// Edges into each node, `None` when every edge goes both ways so they match `forward`
+    reverse: Option<Csr>,
+    /// Number of pairs of nodes joined by an edge in either direction
+    edge_count: usize,
+    /// Pairs of nodes joined a second time, see `GraphBuilder::parallel`
+    parallel: Vec<(T, T)>,
     pub path: RefCell<Path<T>>,
+    /// Nodes expanded by the last search in the order they were expanded, only kept once
+    /// `record_trace` has been called
+    pub trace: RefCell<Option<Vec<T>>>,
+}
+
+/// Follow the parents in a `Path` back from `end`, returning the nodes in order from the start.
+/// Returns `None` if `end` was never reached.
+pub fn walk_path<T>(path: &Path<T>, end: &T) -> Option<Vec<T>>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord,
+{
+    let mut nodes = vec![path.get(end)?.value.clone()];
+
+    while let Some(parent) = path.get(nodes.last()?).and_then(|node| node.parent.clone()) {
+        nodes.push(parent);
+    }
+
+    nodes.reverse();
+
+    Some(nodes)
+}
+
+/// Join the trees of a search from each end where they meet. Nodes on the way from `meet` to
+/// the end are re-parented so `walk_path` from the end leads back to the start through `meet`.
+fn join_paths<T>(mut forward: Path<T>, backward: &Path<T>, meet: &T) -> Path<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord,
+{
+    let mut prev = meet.clone();
+
+    while let Some(next) = backward.get(&prev).and_then(|node| node.parent.clone()) {
+        forward.insert(
+            next.clone(),
+            Node {
+                value: next.clone(),
+                parent: Some(prev),
+                children: None,
+            },
+        );
+        prev = next;
+    }
+
+    forward
+}
+
+/// `Path` for a route listed in order from the start
+fn path_from_route<T>(route: &[T]) -> Path<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord,
+{
+    let mut path = Path::<T>::new();
+    let mut parent: Option<T> = None;
+
+    for node in route {
+        path.insert(
+            node.clone(),
+            Node {
+                value: node.clone(),
+                parent: parent.replace(node.clone()),
+                children: None,
+            },
+        );
+    }
+
+    path
+}
+
+/// A node held in memory by `Graph::sma_star`
+struct BoundedNode<T> {
+    value: T,
+    parent: Option<usize>,
+    g: usize,
+    /// Lower bound on the cost of a path through this node, backed up from its children
+    f: usize,
+    depth: usize,
+    /// Neighbours that aren't already on the route here, created on the first expansion
+    successors: Option<Vec<(T, usize)>>,
+    /// Index of the next successor to generate
+    next: usize,
+    /// Every successor has been generated at least once, so `f` can be backed up from them
+    complete: bool,
+    children: Vec<usize>,
+    /// `f` of each child dropped to save memory, given back to the child if it's generated again
+    forgotten: HashMap<T, usize>,
 }
 
 #[derive(Debug)]
@@ -23,7 +173,7 @@ pub struct Node<T: PartialEq + Eq + Hash + Clone + Debug + Ord> {
 
 #[derive(Clone, Eq, PartialEq)]
 struct QueueItem<T: PartialEq + Eq + Hash + Clone + Debug + Ord> {
-    cost: i32,
+    cost: usize,
     position: T,
 }
 
@@ -52,109 +202,1016 @@ impl<T> Graph<T>
 where
     T: PartialEq + Eq + Hash + Clone + Debug + Ord,
 {
-    pub fn get_path(&self) -> Ref<Path<T>> {
+    /// Freeze the neighbours & edge weights of each node
+    pub(crate) fn from_adjacency(
+        vertices: HashMap<T, HashMap<T, usize>>,
+        parallel: Vec<(T, T)>,
+    ) -> Self {
+        let mut nodes = vertices.keys().cloned().collect::<Vec<_>>();
+        nodes.sort();
+
+        let indexes = nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| (node.clone(), idx))
+            .collect::<HashMap<_, _>>();
+        let mut forward = vec![Vec::<(usize, usize)>::new(); nodes.len()];
+        let mut reverse = vec![Vec::<(usize, usize)>::new(); nodes.len()];
+        let mut directed = false;
+        let mut edge_count = 0;
+
+        for (from, node) in nodes.iter().enumerate() {
+            for (to_node, weight) in vertices[node].iter() {
+                let to = indexes[to_node];
+                let two_way = vertices[to_node].contains_key(node);
+
+                // A two way edge is counted from the lower index, a one way edge from its start
+                if from <= to || !two_way {
+                    edge_count += 1;
+                }
+
+                directed |= !two_way;
+                forward[from].push((to, *weight));
+                reverse[to].push((from, *weight));
+            }
+        }
+
+        Self {
+            nodes,
+            indexes,
+            forward: Csr::new(forward),
+            reverse: directed.then(|| Csr::new(reverse)),
+            edge_count,
+            parallel,
+            path: RefCell::new(HashMap::new()),
+            trace: RefCell::new(None),
+        }
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Number of pairs of nodes joined by an edge, a two way edge is only counted once
+    pub fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+
+    /// Pairs of nodes joined by more than one edge, see `GraphBuilder::parallel`
+    pub fn parallel_edges(&self) -> &[(T, T)] {
+        &self.parallel
+    }
+
+    /// Whether any edge only goes one way
+    pub fn is_directed(&self) -> bool {
+        self.reverse.is_some()
+    }
+
+    pub fn contains(&self, node: &T) -> bool {
+        self.indexes.contains_key(node)
+    }
+
+    /// Every node in order of their index
+    pub fn nodes(&self) -> &[T] {
+        &self.nodes
+    }
+
+    pub fn index_of(&self, node: &T) -> Option<usize> {
+        self.indexes.get(node).copied()
+    }
+
+    /// Neighbours of the node at `index` and the weight of the edge to each, in order of index
+    pub fn edges_at(&self, index: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.forward.edges(index)
+    }
+
+    /// Nodes with an edge into the node at `index` and the weight of that edge, in order of index
+    pub fn edges_into(&self, index: usize) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.reverse.as_ref().unwrap_or(&self.forward).edges(index)
+    }
+
+    /// Neighbours of `node` and the weight of the edge to each, empty if `node` isn't in the graph
+    pub fn neighbours(&self, node: &T) -> impl Iterator<Item = (&T, usize)> + '_ {
+        self.index_of(node)
+            .into_iter()
+            .flat_map(|index| self.edges_at(index))
+            .map(|(to, weight)| (&self.nodes[to], weight))
+    }
+
+    /// Nodes with an edge into `node` and the weight of each edge, empty if `node` isn't in the
+    /// graph
+    pub fn incoming(&self, node: &T) -> impl Iterator<Item = (&T, usize)> + '_ {
+        self.index_of(node)
+            .into_iter()
+            .flat_map(|index| self.edges_into(index))
+            .map(|(from, weight)| (&self.nodes[from], weight))
+    }
+
+    /// Edges a search from one end of a route follows, the searches from the end (`side` 1) walk
+    /// the edges backwards
+    fn side_edges(&self, node: &T, side: usize) -> impl Iterator<Item = (&T, usize)> + '_ {
+        let csr = match side {
+            0 => &self.forward,
+            _ => self.reverse.as_ref().unwrap_or(&self.forward),
+        };
+
+        self.index_of(node)
+            .into_iter()
+            .flat_map(move |index| csr.edges(index))
+            .map(|(to, weight)| (&self.nodes[to], weight))
+    }
+
+    /// Route through the nodes at the indexes in `parents` from the start to `end`
+    fn route_nodes(&self, parents: &[usize], end: usize) -> Vec<T> {
+        route_to(parents, end)
+            .into_iter()
+            .map(|idx| self.nodes[idx].clone())
+            .collect()
+    }
+
+    pub fn get_path(&self) -> Ref<'_, Path<T>> {
         self.path.borrow()
     }
 
+    /// Start keeping a trace of the nodes each search expands, e.g. to animate the search
+    pub fn record_trace(&self) {
+        self.trace.replace(Some(vec![]));
+    }
+
+    /// The nodes expanded by the last search, empty if `record_trace` hasn't been called
+    pub fn get_trace(&self) -> Vec<T> {
+        self.trace.borrow().clone().unwrap_or_default()
+    }
+
+    fn clear_trace(&self) {
+        if let Some(trace) = self.trace.borrow_mut().as_mut() {
+            trace.clear();
+        }
+    }
+
+    fn trace_expansion(&self, node: &T) {
+        if let Some(trace) = self.trace.borrow_mut().as_mut() {
+            trace.push(node.clone());
+        }
+    }
+
+    /// Weight of the edge between `from` & `to`, `None` if they aren't joined
+    pub fn weight(&self, from: &T, to: &T) -> Option<usize> {
+        let to = self.index_of(to)?;
+
+        self.edges_at(self.index_of(from)?)
+            .find(|(next, _)| *next == to)
+            .map(|(_, weight)| weight)
+    }
+
+    /// Total weight of the edges along `path`, `None` if any step isn't an edge
+    pub fn path_cost(&self, path: &[T]) -> Option<usize> {
+        path.windows(2)
+            .map(|pair| self.weight(&pair[0], &pair[1]))
+            .sum()
+    }
+
+    /// Number of edges between `start` and every node reachable from it, found with a BFS
+    pub fn distances(&self, start: T) -> HashMap<T, usize> {
+        let Some(start) = self.index_of(&start) else {
+            return HashMap::new();
+        };
+
+        let mut distances = vec![usize::MAX; self.len()];
+        let mut queue = VecDeque::from([start]);
+        distances[start] = 0;
+
+        while let Some(v) = queue.pop_front() {
+            for (w, _) in self.edges_at(v) {
+                if distances[w] == usize::MAX {
+                    distances[w] = distances[v] + 1;
+                    queue.push_back(w);
+                }
+            }
+        }
+
+        distances
+            .into_iter()
+            .enumerate()
+            .filter(|(_, distance)| *distance != usize::MAX)
+            .map(|(idx, distance)| (self.nodes[idx].clone(), distance))
+            .collect()
+    }
+
+    /// Path with the fewest edges from `start` to `end`. The path only holds the nodes along the
+    /// route rather than every node the search reached, `record_trace` logs those.
     pub fn bfs(&self, start: T, end: T) -> Option<Path<T>> {
-        let mut nodes_checked = 0;
-        let mut stack = VecDeque::<T>::new();
-        let mut visited = HashSet::<T>::new();
-        let mut map_path = HashMap::<T, Node<T>>::new();
-
-        visited.insert(start.clone());
-        stack.push_front(start.clone());
-        map_path.insert(
-            start.clone(),
-            Node {
-                value: start.clone(),
-                parent: None,
-                children: None,
-            },
+        self.clear_trace();
+
+        let (start, end) = (self.index_of(&start)?, self.index_of(&end)?);
+        let mut queue = VecDeque::from([start]);
+        let mut visited = BitSet::new(self.len());
+        let mut parents = vec![NO_PARENT; self.len()];
+
+        visited.insert(start);
+
+        while let Some(v) = queue.pop_front() {
+            self.trace_expansion(&self.nodes[v]);
+
+            if v == end {
+                return Some(path_from_route(&self.route_nodes(&parents, end)));
+            }
+
+            for (w, _) in self.edges_at(v) {
+                if visited.insert(w) {
+                    parents[w] = v;
+                    queue.push_back(w);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// A* over the node indexes, the heuristic of each node is worked out the first time it's
+    /// reached and cached for when it's reached again by a cheaper route
+    fn cheapest_route(
+        &self,
+        start: &T,
+        end: &T,
+        heuristic_fn: &dyn Fn(&T, &T) -> usize,
+    ) -> Option<Vec<T>> {
+        let (start, end) = (self.index_of(start)?, self.index_of(end)?);
+        let goal = &self.nodes[end];
+
+        let mut queue = BinaryHeap::from([Reverse((0, start))]);
+        let mut closed = BitSet::new(self.len());
+        let mut g_score = vec![usize::MAX; self.len()];
+        let mut parents = vec![NO_PARENT; self.len()];
+        let mut estimates = vec![None; self.len()];
+
+        g_score[start] = 0;
+
+        while let Some(Reverse((_, position))) = queue.pop() {
+            // Stale entry for a node that has already been reached more cheaply
+            if !closed.insert(position) {
+                continue;
+            }
+
+            self.trace_expansion(&self.nodes[position]);
+
+            if position == end {
+                return Some(self.route_nodes(&parents, end));
+            }
+
+            for (neighbor, weight) in self.edges_at(position) {
+                let tentative_g_score = g_score[position] + weight;
+
+                if tentative_g_score < g_score[neighbor] {
+                    g_score[neighbor] = tentative_g_score;
+                    parents[neighbor] = position;
+
+                    let estimate = *estimates[neighbor]
+                        .get_or_insert_with(|| heuristic_fn(&self.nodes[neighbor], goal));
+                    queue.push(Reverse((tentative_g_score + estimate, neighbor)));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Based on https://en.wikipedia.org/wiki/Dijkstra%27s_algorithm#Using_a_priority_queue
+    /// Finds the path from `start` to `end` with the lowest total weight, holding only the nodes
+    /// along the route like `bfs`
+    pub fn dijkstra(&self, start: T, end: T) -> Option<Path<T>> {
+        self.clear_trace();
+
+        self.cheapest_route(&start, &end, &|_, _| 0)
+            .map(|route| path_from_route(&route))
+    }
+
+    /// Based on https://en.wikipedia.org/wiki/A*_search_algorithm#Pseudocode
+    /// The cost of each step is the weight of the edge, so `heuristic_fn` must not overestimate
+    /// the total weight to `end` for the path to be the cheapest. The path is kept in
+    /// `Graph::path`, empty if `end` can't be reached. Like `bfs` it only holds the nodes along the
+    /// route, so its length is the number of nodes on the route.
+    pub fn astar(&self, start: T, end: T, heuristic_fn: &dyn Fn(&T, &T) -> usize) {
+        self.clear_trace();
+
+        let route = self.cheapest_route(&start, &end, heuristic_fn);
+        self.path.replace(
+            route
+                .map(|route| path_from_route(&route))
+                .unwrap_or_default(),
         );
+    }
+
+    /// BFS from both ends at once, expanding a whole level of whichever side has the smaller
+    /// frontier. Once the level touches the other side the shortest join found in it is the
+    /// shortest path, so the search stops there.
+    pub fn bidirectional_bfs(&self, start: T, end: T) -> Option<Path<T>> {
+        self.clear_trace();
+
+        let mut sides = [start.clone(), end].map(|from| {
+            let path = HashMap::from([(
+                from.clone(),
+                Node {
+                    value: from.clone(),
+                    parent: None,
+                    children: None,
+                },
+            )]);
+
+            (HashMap::from([(from.clone(), 0)]), path, vec![from])
+        });
 
-        while !stack.is_empty() {
-            nodes_checked += 1;
-            if let Some(v) = stack.pop_front() {
-                if v == end {
-                    println!("Nodes Checked: {}", nodes_checked);
-                    return Some(map_path);
-                } else {
-                    if let Some(edges) = self.vertices.get(&v) {
-                        for w in edges.iter() {
-                            if !visited.contains(&w) {
-                                visited.insert(w.clone());
-                                stack.push_back(w.clone());
-                                map_path.insert(
-                                    w.clone(),
-                                    Node {
-                                        value: w.clone(),
-                                        parent: Some(v.clone()),
-                                        children: None,
-                                    },
-                                );
-                            }
+        if sides[1].0.contains_key(&start) {
+            let [(_, path, _), _] = sides;
+            return Some(path);
+        }
+
+        while !sides[0].2.is_empty() && !sides[1].2.is_empty() {
+            let side = usize::from(sides[1].2.len() < sides[0].2.len());
+            let [this, other] = match side {
+                0 => sides.each_mut(),
+                _ => {
+                    let [a, b] = sides.each_mut();
+                    [b, a]
+                }
+            };
+            let (distances, path, frontier) = this;
+            let mut next = Vec::<T>::new();
+            let mut best: Option<(usize, T)> = None;
+
+            for v in frontier.drain(..) {
+                self.trace_expansion(&v);
+
+                let distance = distances[&v] + 1;
+
+                for (w, _) in self.side_edges(&v, side) {
+                    if distances.contains_key(w) {
+                        continue;
+                    }
+
+                    distances.insert(w.clone(), distance);
+                    path.insert(
+                        w.clone(),
+                        Node {
+                            value: w.clone(),
+                            parent: Some(v.clone()),
+                            children: None,
+                        },
+                    );
+                    next.push(w.clone());
+
+                    if let Some(rest) = other.0.get(w) {
+                        if best
+                            .as_ref()
+                            .is_none_or(|(length, _)| distance + rest < *length)
+                        {
+                            best = Some((distance + rest, w.clone()));
                         }
                     }
                 }
             }
+
+            *frontier = next;
+
+            if let Some((_, meet)) = best {
+                let [(_, forward, _), (_, backward, _)] = sides;
+                return Some(join_paths(forward, &backward, &meet));
+            }
         }
 
         None
     }
 
-    /// Based on https://en.wikipedia.org/wiki/A*_search_algorithm#Pseudocode
-    pub fn astar(
+    /// A* from both ends at once, expanding whichever side has the smaller queue. Each side uses
+    /// the average of the heuristic towards its target and away from its source, as described in
+    /// "Computing the Shortest Path: A* Search Meets Graph Theory" (Goldberg & Harrelson), so the
+    /// two searches agree on the cost of every edge and can stop as soon as the cheapest
+    /// node left on each side adds up to the best join found. `heuristic_fn` must never
+    /// overestimate and must be consistent, e.g. the Manhattan distance on a grid.
+    pub fn bidirectional_astar(
         &self,
         start: T,
         end: T,
-        heuristic_fn: &dyn Fn(&T, &T) -> i32,
-        distance_fn: &dyn Fn(&T, &T) -> i32,
-    ) {
-        let mut nodes_checked = 0;
-        let mut queue = BinaryHeap::<QueueItem<T>>::new();
+        heuristic_fn: &dyn Fn(&T, &T) -> usize,
+    ) -> Option<Path<T>> {
+        self.clear_trace();
 
-        let mut g_score = HashMap::<T, i32>::new();
-        g_score.insert(start.clone(), 0);
+        let ends = [start.clone(), end.clone()];
+        // Twice the average heuristic so it stays a whole number, it's shifted up by the
+        // heuristic between the ends so it's never negative
+        let potential = |node: &T, side: usize| {
+            (heuristic_fn(node, &ends[1 - side]) + heuristic_fn(&ends[side], &ends[1 - side]))
+                .saturating_sub(heuristic_fn(node, &ends[side]))
+        };
+        let shift = heuristic_fn(&ends[0], &ends[1]) + heuristic_fn(&ends[1], &ends[0]);
+        let mut sides = [start, end].map(|from| {
+            let path = HashMap::from([(
+                from.clone(),
+                Node {
+                    value: from.clone(),
+                    parent: None,
+                    children: None,
+                },
+            )]);
 
-        queue.push(QueueItem {
-            cost: *g_score.get(&start).unwrap_or(&0),
-            position: start.clone(),
+            (HashMap::from([(from.clone(), 0)]), path, BinaryHeap::new())
         });
 
-        while let Some(QueueItem { cost: _, position }) = queue.pop() {
-            nodes_checked += 1;
-            if position == end {
+        for (side, (_, _, queue)) in sides.iter_mut().enumerate() {
+            queue.push(QueueItem {
+                cost: potential(&ends[side], side),
+                position: ends[side].clone(),
+            });
+        }
+
+        let mut best: Option<(usize, T)> = None;
+
+        if ends[0] == ends[1] {
+            best = Some((0, ends[0].clone()));
+        }
+
+        loop {
+            // Drop entries that have been replaced by a cheaper route to the same node
+            for (side, (g_score, _, queue)) in sides.iter_mut().enumerate() {
+                while let Some(item) = queue.peek() {
+                    if item.cost == 2 * g_score[&item.position] + potential(&item.position, side) {
+                        break;
+                    }
+
+                    queue.pop();
+                }
+            }
+
+            let (Some(forward), Some(backward)) = (sides[0].2.peek(), sides[1].2.peek()) else {
+                break;
+            };
+
+            // The lowest keys add up to twice the cost of the cheapest path that could still be
+            // found, plus the shift from both sides
+            let bound = forward.cost + backward.cost;
+
+            if best
+                .as_ref()
+                .is_some_and(|(cost, _)| bound >= 2 * cost + shift)
+            {
                 break;
             }
 
-            if let Some(edges) = self.vertices.get(&position) {
-                for neighbor in edges.iter() {
-                    let current_g_score = *g_score.get(&position).unwrap();
-                    let tentative_g_score = current_g_score + distance_fn(&position, &neighbor);
-
-                    if tentative_g_score < *g_score.get(&neighbor).unwrap_or(&i32::MAX) {
-                        let mut path = self.path.borrow_mut();
-                        path.insert(
-                            neighbor.clone(),
-                            Node {
-                                value: neighbor.clone(),
-                                parent: Some(position.clone()),
-                                children: None,
-                            },
-                        );
-
-                        g_score.insert(neighbor.clone(), tentative_g_score);
-
-                        queue.push(QueueItem {
-                            cost: tentative_g_score + heuristic_fn(&neighbor, &end),
-                            position: neighbor.clone(),
-                        })
+            let side = usize::from(sides[1].2.len() < sides[0].2.len());
+            let [this, other] = match side {
+                0 => sides.each_mut(),
+                _ => {
+                    let [a, b] = sides.each_mut();
+                    [b, a]
+                }
+            };
+            let (g_score, path, queue) = this;
+            let QueueItem { position, .. } = queue.pop().unwrap();
+
+            self.trace_expansion(&position);
+
+            let current_g_score = g_score[&position];
+
+            for (neighbor, weight) in self.side_edges(&position, side) {
+                let tentative_g_score = current_g_score + weight;
+
+                if tentative_g_score >= *g_score.get(neighbor).unwrap_or(&usize::MAX) {
+                    continue;
+                }
+
+                g_score.insert(neighbor.clone(), tentative_g_score);
+                path.insert(
+                    neighbor.clone(),
+                    Node {
+                        value: neighbor.clone(),
+                        parent: Some(position.clone()),
+                        children: None,
+                    },
+                );
+                queue.push(QueueItem {
+                    cost: 2 * tentative_g_score + potential(neighbor, side),
+                    position: neighbor.clone(),
+                });
+
+                if let Some(rest) = other.0.get(neighbor) {
+                    let cost = tentative_g_score + rest;
+
+                    if best.as_ref().is_none_or(|(lowest, _)| cost < *lowest) {
+                        best = Some((cost, neighbor.clone()));
                     }
                 }
             }
         }
 
-        println!("Nodes Checked: {}", nodes_checked);
+        let (_, meet) = best?;
+        let [(_, forward, _), (_, backward, _)] = sides;
+
+        Some(join_paths(forward, &backward, &meet))
+    }
+
+    /// Iterative deepening A*, a depth first search cut off at a limit on `g + h` that's raised
+    /// to the lowest cut off value after each pass. Only the current route is held in memory, at
+    /// the cost of searching the same nodes again on each pass.
+    ///
+    /// Nodes already on the current route are skipped, but nothing else is remembered between
+    /// branches, so on a graph with a lot of loops it can take a very long time to give up when
+    /// `end` can't be reached.
+    pub fn ida_star(
+        &self,
+        start: T,
+        end: T,
+        heuristic_fn: &dyn Fn(&T, &T) -> usize,
+    ) -> Option<Path<T>> {
+        self.clear_trace();
+
+        let mut limit = heuristic_fn(&start, &end);
+
+        loop {
+            let mut next_limit = usize::MAX;
+            // Current route from the start, with the neighbours of each node still to try
+            let mut route = vec![(start.clone(), 0, self.sorted_edges(&start))];
+            let mut on_route = HashSet::from([start.clone()]);
+
+            self.trace_expansion(&start);
+
+            while let Some((node, g, edges)) = route.last_mut() {
+                if *node == end {
+                    let route = route
+                        .into_iter()
+                        .map(|(node, _, _)| node)
+                        .collect::<Vec<_>>();
+                    return Some(path_from_route(&route));
+                }
+
+                let Some((neighbor, weight)) = edges.pop() else {
+                    let (node, _, _) = route.pop().unwrap();
+                    on_route.remove(&node);
+                    continue;
+                };
+
+                if on_route.contains(&neighbor) {
+                    continue;
+                }
+
+                let g = *g + weight;
+                let f = g + heuristic_fn(&neighbor, &end);
+
+                if f > limit {
+                    next_limit = next_limit.min(f);
+                    continue;
+                }
+
+                self.trace_expansion(&neighbor);
+
+                on_route.insert(neighbor.clone());
+                route.push((neighbor.clone(), g, self.sorted_edges(&neighbor)));
+            }
+
+            if next_limit == usize::MAX {
+                return None;
+            }
+
+            limit = next_limit;
+        }
+    }
+
+    /// Edges out of `node` in reverse order, so popping them gives a repeatable order
+    fn sorted_edges(&self, node: &T) -> Vec<(T, usize)> {
+        let mut edges = self
+            .neighbours(node)
+            .map(|(n, w)| (n.clone(), w))
+            .collect::<Vec<_>>();
+
+        edges.sort_by(|a, b| b.cmp(a));
+        edges
+    }
+
+    /// Simplified memory-bounded A* (SMA*), based on
+    /// [this description](https://en.wikipedia.org/wiki/SMA*). At most `max_nodes` search nodes
+    /// are held at once, when it's full the leaf with the highest `f` is dropped and its parent
+    /// remembers its cost so it can be regenerated if it turns out to be needed.
+    ///
+    /// The path found is the cheapest one as long as it has at most `max_nodes` nodes, longer
+    /// paths can't fit in memory so `None` is returned if that's the only way to `end`. Like
+    /// `ida_star` it's a tree search, so an unreachable `end` is slow to rule out.
+    pub fn sma_star(
+        &self,
+        start: T,
+        end: T,
+        heuristic_fn: &dyn Fn(&T, &T) -> usize,
+        max_nodes: usize,
+    ) -> Option<Path<T>> {
+        self.clear_trace();
+
+        let max_nodes = max_nodes.max(2);
+        let mut nodes = HashMap::<usize, BoundedNode<T>>::new();
+        let mut next_id = 1;
+        // Ordered by lowest f then deepest, so the best node is first and the worst is last
+        let mut open = BTreeSet::<(usize, Reverse<usize>, usize)>::new();
+
+        nodes.insert(
+            0,
+            BoundedNode {
+                value: start.clone(),
+                parent: None,
+                g: 0,
+                f: heuristic_fn(&start, &end),
+                depth: 0,
+                successors: None,
+                next: 0,
+                complete: false,
+                children: vec![],
+                forgotten: HashMap::new(),
+            },
+        );
+        open.insert((nodes[&0].f, Reverse(0), 0));
+
+        let key = |node: &BoundedNode<T>, id: usize| (node.f, Reverse(node.depth), id);
+
+        while let Some(&(f, _, best)) = open.first() {
+            if f == usize::MAX {
+                break;
+            }
+
+            if nodes[&best].value == end {
+                let mut route = vec![];
+                let mut id = Some(best);
+
+                while let Some(node) = id.map(|id| &nodes[&id]) {
+                    route.push(node.value.clone());
+                    id = node.parent;
+                }
+
+                route.reverse();
+                return Some(path_from_route(&route));
+            }
+
+            self.trace_expansion(&nodes[&best].value);
+
+            // Routes never go back through a node already on them
+            let mut ancestors = HashSet::<T>::new();
+            let mut ancestor = nodes[&best].parent;
+
+            while let Some(id) = ancestor {
+                ancestors.insert(nodes[&id].value.clone());
+                ancestor = nodes[&id].parent;
+            }
+
+            let in_memory = nodes[&best]
+                .children
+                .iter()
+                .map(|child| nodes[child].value.clone())
+                .collect::<HashSet<_>>();
+            let node = nodes.get_mut(&best).unwrap();
+            let successors = node.successors.get_or_insert_with(|| {
+                self.sorted_edges(&node.value)
+                    .into_iter()
+                    .filter(|(value, _)| !ancestors.contains(value))
+                    .collect()
+            });
+            let total = successors.len();
+
+            // Skip successors that are already in memory. Once every successor has been
+            // generated, start again from the first to bring back the ones that were dropped.
+            while node.children.len() < total {
+                if node.next == total {
+                    node.next = 0;
+                }
+
+                if !in_memory.contains(&successors[node.next].0) {
+                    break;
+                }
+
+                node.next += 1;
+            }
+
+            if node.children.len() == total {
+                open.remove(&key(node, best));
+
+                // Nothing to generate, this is a dead end
+                if total == 0 {
+                    node.f = usize::MAX;
+                    open.insert(key(node, best));
+
+                    let parent = node.parent;
+                    self.back_up(&mut nodes, &mut open, parent);
+                }
+
+                continue;
+            }
+
+            let (value, weight) = successors[node.next].clone();
+            node.next += 1;
+
+            let g = node.g + weight;
+            let depth = node.depth + 1;
+            let remembered = node.forgotten.remove(&value).unwrap_or(0);
+            let f = match value != end && depth + 1 >= max_nodes {
+                // No room to go any deeper
+                true => usize::MAX,
+                false => node.f.max(g + heuristic_fn(&value, &end)).max(remembered),
+            };
+
+            let child = next_id;
+            next_id += 1;
+            node.children.push(child);
+
+            node.complete |= node.next == total;
+            let complete = node.complete;
+            let all_in_memory = node.children.len() == total;
+
+            nodes.insert(
+                child,
+                BoundedNode {
+                    value,
+                    parent: Some(best),
+                    g,
+                    f,
+                    depth,
+                    successors: None,
+                    next: 0,
+                    complete: false,
+                    children: vec![],
+                    forgotten: HashMap::new(),
+                },
+            );
+
+            if complete {
+                self.back_up(&mut nodes, &mut open, Some(best));
+            }
+
+            if all_in_memory {
+                open.remove(&key(&nodes[&best], best));
+            }
+
+            // Make room by dropping the worst leaf, apart from the child just made
+            while nodes.len() > max_nodes {
+                let worst = open
+                    .iter()
+                    .rev()
+                    .map(|(_, _, id)| *id)
+                    .find(|id| nodes[id].children.is_empty() && nodes[id].parent.is_some());
+
+                let Some(worst) = worst else {
+                    break;
+                };
+
+                let dropped = nodes.remove(&worst).unwrap();
+                open.remove(&key(&dropped, worst));
+
+                let parent_id = dropped.parent.unwrap();
+                let parent = nodes.get_mut(&parent_id).unwrap();
+                parent.children.retain(|id| *id != worst);
+                parent.forgotten.insert(dropped.value, dropped.f);
+                open.insert(key(parent, parent_id));
+            }
+
+            open.insert(key(&nodes[&child], child));
+        }
+
+        None
+    }
+
+    /// Set the `f` of `id` to the lowest `f` of its children, including dropped ones, once they've
+    /// all been generated, passing the change up the tree
+    fn back_up(
+        &self,
+        nodes: &mut HashMap<usize, BoundedNode<T>>,
+        open: &mut BTreeSet<(usize, Reverse<usize>, usize)>,
+        mut id: Option<usize>,
+    ) {
+        while let Some(current) = id {
+            let node = &nodes[&current];
+            if !node.complete {
+                break;
+            }
+
+            let lowest = node
+                .children
+                .iter()
+                .map(|child| nodes[child].f)
+                .min()
+                .unwrap_or(usize::MAX)
+                .min(node.forgotten.values().min().copied().unwrap_or(usize::MAX));
+
+            if lowest == node.f {
+                break;
+            }
+
+            let key = (node.f, Reverse(node.depth), current);
+            let was_open = open.remove(&key);
+            let node = nodes.get_mut(&current).unwrap();
+            node.f = lowest;
+
+            if was_open {
+                open.insert((node.f, Reverse(node.depth), current));
+            }
+
+            id = node.parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::graph::builder::GraphBuilder;
+
+    #[test]
+    fn test_weighted_searches_take_cheapest_path() {
+        // The direct edge from a to c is longer than going through b
+        let graph = GraphBuilder::from_weighted_edges(vec![
+            ('a', 'c', 10),
+            ('a', 'b', 3),
+            ('b', 'c', 4),
+            ('c', 'd', 1),
+        ])
+        .build();
+
+        let path = walk_path(&graph.dijkstra('a', 'd').unwrap(), &'d').unwrap();
+        assert_eq!(vec!['a', 'b', 'c', 'd'], path);
+        assert_eq!(Some(8), graph.path_cost(&path));
+
+        graph.astar('a', 'd', &|_, _| 0);
+        assert_eq!(Some(path), walk_path(&graph.get_path(), &'d'));
+
+        // BFS takes the fewest edges regardless of weight
+        let path = walk_path(&graph.bfs('a', 'd').unwrap(), &'d').unwrap();
+        assert_eq!(Some(11), graph.path_cost(&path));
+
+        let path = graph.bidirectional_astar('a', 'd', &|_, _| 0).unwrap();
+        assert_eq!(Some(vec!['a', 'b', 'c', 'd']), walk_path(&path, &'d'));
+    }
+
+    #[test]
+    fn test_built_graph_is_compressed() {
+        let graph = GraphBuilder::from_weighted_edges(vec![('c', 'a', 2), ('a', 'b', 1)])
+            .insert_node('d')
+            .build();
+
+        assert_eq!((4, 2), (graph.len(), graph.edge_count()));
+        assert_eq!(&['a', 'b', 'c', 'd'], graph.nodes());
+        assert_eq!(Some(2), graph.index_of(&'c'));
+        assert_eq!(vec![(1, 1), (2, 2)], graph.edges_at(0).collect::<Vec<_>>());
+        assert_eq!(vec![(&'a', 2)], graph.neighbours(&'c').collect::<Vec<_>>());
+        assert_eq!(0, graph.neighbours(&'d').count());
+        assert_eq!(0, graph.neighbours(&'e').count());
+        assert_eq!(
+            (Some(2), None),
+            (graph.weight(&'a', &'c'), graph.weight(&'a', &'d'))
+        );
+        assert!(graph.bfs('a', 'd').is_none());
+    }
+
+    #[test]
+    fn test_bidirectional_bfs_matches_bfs() {
+        // A 6x6 grid with every edge, so there are lots of shortest paths to choose from
+        let mut builder = GraphBuilder::<(usize, usize)>::new();
+
+        for x in 0..6 {
+            for y in 0..6 {
+                builder.add_node((x, y));
+                builder.add_edge((x, y), (x.saturating_sub(1), y));
+                builder.add_edge((x, y), (x, y.saturating_sub(1)));
+            }
+        }
+
+        let graph = builder.build();
+        let expected = walk_path(&graph.bfs((0, 0), (5, 4)).unwrap(), &(5, 4)).unwrap();
+
+        graph.record_trace();
+        let path = walk_path(&graph.bidirectional_bfs((0, 0), (5, 4)).unwrap(), &(5, 4)).unwrap();
+
+        assert_eq!(expected.len(), path.len());
+        assert_eq!((0, 0), path[0]);
+        assert!(graph.path_cost(&path).is_some());
+        assert!(graph.get_trace().len() < 36);
+
+        let same = graph.bidirectional_bfs((2, 2), (2, 2)).unwrap();
+        assert_eq!(Some(vec![(2, 2)]), walk_path(&same, &(2, 2)));
+    }
+
+    #[test]
+    fn test_directed_edges_only_go_one_way() {
+        // A one way shortcut 0 -> 3, the way back from 3 goes round through 4 & 5
+        let graph = GraphBuilder::from_nodes((0..6).collect())
+            .insert_directed_edge(0, 3, 1)
+            .insert_edge(0, 1)
+            .insert_edge(1, 2)
+            .insert_edge(2, 3)
+            .insert_directed_edge(3, 4, 1)
+            .insert_directed_edge(4, 5, 1)
+            .insert_directed_edge(5, 0, 1)
+            .build();
+
+        assert!(graph.is_directed());
+        assert_eq!(7, graph.edge_count());
+        assert_eq!(
+            vec![(&0, 1)],
+            graph
+                .incoming(&3)
+                .filter(|(n, _)| **n != 2)
+                .collect::<Vec<_>>()
+        );
+
+        for (start, end, length) in [(0, 3, 2), (3, 0, 4), (4, 3, 4), (0, 5, 4), (2, 0, 3)] {
+            let heuristic = |_: &usize, _: &usize| 0;
+            let routes = [
+                graph.bfs(start, end),
+                graph.bidirectional_bfs(start, end),
+                graph.bidirectional_astar(start, end, &heuristic),
+            ]
+            .map(|path| walk_path(&path.unwrap(), &end).unwrap());
+
+            for route in routes {
+                assert_eq!(length, route.len(), "{:?}", route);
+                assert_eq!(Some(length - 1), graph.path_cost(&route));
+            }
+        }
+
+        assert_eq!(1, graph.connected_components().len());
+        assert!(graph.has_cycle());
+    }
+
+    #[test]
+    fn test_bidirectional_astar_with_a_heuristic_matches_dijkstra() {
+        use crate::algo::{
+            grid::{Grid, Topology},
+            maze::MazeGenerate,
+            RandomisedDFS,
+        };
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // Braided so there are loops and more than one route to choose between
+        for seed in 0..20 {
+            let topology = [Topology::Plane, Topology::Torus][seed as usize % 2];
+            let mut maze =
+                RandomisedDFS::from_grid(Grid::with_topology(15, 15, topology)).with_seed(seed);
+            maze.generate();
+            maze.grid.braid_with(0.5, &mut StdRng::seed_from_u64(seed));
+
+            let grid = &maze.grid;
+            let graph = GraphBuilder::<(usize, usize)>::from(grid).build();
+            let heuristic =
+                |a: &(usize, usize), b: &(usize, usize)| grid.manhattan_distance(*a, *b);
+
+            for (start, end) in [((0, 0), (14, 14)), ((3, 11), (12, 2)), ((7, 7), (0, 14))] {
+                let cost = |path: Option<Path<(usize, usize)>>| {
+                    graph.path_cost(&walk_path(&path.unwrap(), &end).unwrap())
+                };
+
+                assert_eq!(
+                    cost(graph.dijkstra(start, end)),
+                    cost(graph.bidirectional_astar(start, end, &heuristic)),
+                    "seed {} from {:?} to {:?}",
+                    seed,
+                    start,
+                    end
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_memory_bounded_searches_find_cheapest_path() {
+        // Every edge of a 5x5 grid, with the edges in the middle row made expensive
+        let mut builder = GraphBuilder::<(usize, usize)>::new();
+
+        for x in 0..5 {
+            for y in 0..5 {
+                builder.add_node((x, y));
+
+                let weight = if y == 2 { 5 } else { 1 };
+                if x > 0 {
+                    builder.add_weighted_edge((x, y), (x - 1, y), weight);
+                }
+                if y > 0 {
+                    builder.add_edge((x, y), (x, y - 1));
+                }
+            }
+        }
+
+        let graph = builder.build();
+        let (start, end) = ((0, 2), (4, 2));
+        let heuristic =
+            |a: &(usize, usize), b: &(usize, usize)| a.0.abs_diff(b.0) + a.1.abs_diff(b.1);
+        let cost = |path: Option<Path<(usize, usize)>>| {
+            path.and_then(|path| walk_path(&path, &end))
+                .and_then(|route| graph.path_cost(&route))
+        };
+
+        // Stepping up a row to go along is cheaper than the middle row
+        assert_eq!(Some(6), cost(graph.dijkstra(start, end)));
+        assert_eq!(Some(6), cost(graph.ida_star(start, end, &heuristic)));
+        assert_eq!(Some(6), cost(graph.sma_star(start, end, &heuristic, 1000)));
+        // The cheapest path has 7 nodes, with room for one less it has to settle for the middle row
+        assert_eq!(Some(6), cost(graph.sma_star(start, end, &heuristic, 7)));
+        assert_eq!(Some(20), cost(graph.sma_star(start, end, &heuristic, 6)));
+        assert_eq!(None, cost(graph.sma_star(start, end, &heuristic, 4)));
     }
 }
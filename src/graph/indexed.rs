@@ -0,0 +1,208 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use crate::algo::grid::Grid;
+use crate::algo::maze::Direction;
+use crate::util::{route_to, BitSet, NO_PARENT};
+
+/// Graph with nodes numbered `0..len`, e.g. the cell ids of a `Grid`. Searches keep
+/// their visited nodes & parents in flat arrays indexed by node instead of hash maps, which is
+/// much faster on large mazes where most of the graph gets explored.
+#[derive(Debug, Clone, Default)]
+pub struct IndexedGraph {
+    /// Neighbours of each node and the weight of the edge to them
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl IndexedGraph {
+    /// Graph of `len` nodes without any edges
+    pub fn new(len: usize) -> Self {
+        Self {
+            edges: vec![vec![]; len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize) {
+        self.add_weighted_edge(from, to, 1);
+    }
+
+    pub fn add_weighted_edge(&mut self, from: usize, to: usize, weight: usize) {
+        self.edges[from].push((to, weight));
+        self.edges[to].push((from, weight));
+    }
+
+    /// Add an edge that can only be travelled from `from` to `to`
+    pub fn add_directed_edge(&mut self, from: usize, to: usize, weight: usize) {
+        self.edges[from].push((to, weight));
+    }
+
+    /// Neighbours of `node` and the weight of the edge to each of them
+    pub fn neighbours(&self, node: usize) -> &[(usize, usize)] {
+        &self.edges[node]
+    }
+
+    /// Number of edges between `start` and every node, `None` for nodes it can't reach
+    pub fn distances(&self, start: usize) -> Vec<Option<usize>> {
+        let mut distances = vec![None; self.len()];
+        let mut queue = VecDeque::from([start]);
+        distances[start] = Some(0);
+
+        while let Some(node) = queue.pop_front() {
+            let distance = distances[node].unwrap_or(0);
+
+            for (next, _) in self.neighbours(node) {
+                if distances[*next].is_none() {
+                    distances[*next] = Some(distance + 1);
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Route with the fewest edges from `start` to `end`, listed from the start
+    pub fn bfs(&self, start: usize, end: usize) -> Option<Vec<usize>> {
+        let mut visited = BitSet::new(self.len());
+        let mut parents = vec![NO_PARENT; self.len()];
+        let mut queue = VecDeque::from([start]);
+        visited.insert(start);
+
+        while let Some(node) = queue.pop_front() {
+            if node == end {
+                return Some(route_to(&parents, end));
+            }
+
+            for (next, _) in self.neighbours(node) {
+                if visited.insert(*next) {
+                    parents[*next] = node;
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Route with the lowest total weight from `start` to `end`
+    pub fn dijkstra(&self, start: usize, end: usize) -> Option<Vec<usize>> {
+        self.astar(start, end, &|_| 0)
+    }
+
+    /// A* towards `end`, `heuristic` is the estimated cost from a node to `end` and must never
+    /// overestimate it for the route to be the cheapest
+    pub fn astar(
+        &self,
+        start: usize,
+        end: usize,
+        heuristic: &dyn Fn(usize) -> usize,
+    ) -> Option<Vec<usize>> {
+        let mut closed = BitSet::new(self.len());
+        let mut costs = vec![usize::MAX; self.len()];
+        let mut parents = vec![NO_PARENT; self.len()];
+        let mut queue = BinaryHeap::from([Reverse((heuristic(start), start))]);
+        costs[start] = 0;
+
+        while let Some(Reverse((_, node))) = queue.pop() {
+            if node == end {
+                return Some(route_to(&parents, end));
+            }
+
+            // Stale entries left behind when a cheaper route to the node was found
+            if !closed.insert(node) {
+                continue;
+            }
+
+            for (next, weight) in self.neighbours(node) {
+                let cost = costs[node] + weight;
+
+                if cost < costs[*next] {
+                    costs[*next] = cost;
+                    parents[*next] = node;
+                    queue.push(Reverse((cost + heuristic(*next), *next)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Cell level graph of the maze with each node numbered by `Grid::cell_id`, a weave tunnel joins
+/// the cells at either end of it and one way doors are edges that only go one way
+impl From<&Grid> for IndexedGraph {
+    fn from(grid: &Grid) -> Self {
+        let mut graph = IndexedGraph::new(grid.rows * grid.cols);
+
+        for y in 0..grid.rows {
+            for x in 0..grid.cols {
+                for dir in [Direction::Right, Direction::Bottom] {
+                    let Some((nx, ny)) = grid.passage_to(x, y, dir) else {
+                        continue;
+                    };
+
+                    let (from, to) = (grid.cell_id(x, y), grid.cell_id(nx, ny));
+
+                    match grid.is_one_way(x, y, dir) {
+                        false => graph.add_edge(from, to),
+                        true if grid.can_pass(x, y, dir) => graph.add_directed_edge(from, to, 1),
+                        true => graph.add_directed_edge(to, from, 1),
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+    use crate::graph::{builder::GraphBuilder, graph::walk_path};
+
+    #[test]
+    fn test_searches_match_generic_graph() {
+        let mut maze = RandomisedDFS::from_grid_size(20, 20);
+        maze.generate();
+        maze.grid.braid(0.5);
+
+        let grid = &maze.grid;
+        let indexed = IndexedGraph::from(grid);
+        let graph = GraphBuilder::<(usize, usize)>::from(grid).build();
+        let end = (19, 19);
+        let expected = walk_path(&graph.bfs((0, 0), end).unwrap(), &end).unwrap();
+
+        let heuristic = |id: usize| grid.manhattan_distance((id % 20, id / 20), end);
+
+        // Loops mean there can be more than one shortest route, so only the lengths are compared
+        let bfs = indexed.bfs(0, 399).unwrap();
+        assert_eq!(expected.len(), bfs.len());
+        assert_eq!((Some(&0), Some(&399)), (bfs.first(), bfs.last()));
+        assert_eq!(
+            expected.len(),
+            indexed.astar(0, 399, &heuristic).unwrap().len()
+        );
+        assert_eq!(expected.len(), indexed.dijkstra(0, 399).unwrap().len());
+        assert_eq!(Some(expected.len() - 1), indexed.distances(0)[399]);
+
+        // Every step of the route is an edge
+        for pair in bfs.windows(2) {
+            assert!(indexed
+                .neighbours(pair[0])
+                .iter()
+                .any(|(n, _)| *n == pair[1]));
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Debug, Display},
+    hash::Hash,
+};
+
+use super::graph::Graph;
+
+/// Summary of the shape of a graph, distances are counted in edges
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct GraphMetrics<T> {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Groups of nodes that can't reach each other
+    pub components: usize,
+    /// Connected without any loops, e.g. the graph of a perfect maze
+    pub is_tree: bool,
+    /// Longest shortest path between two nodes
+    pub diameter: usize,
+    /// Lowest eccentricity of any node
+    pub radius: usize,
+    /// Nodes with the lowest eccentricity
+    pub centre: Vec<T>,
+    /// Two nodes `diameter` apart, the ends of the longest path
+    pub ends: Option<(T, T)>,
+}
+
+impl<T: Debug> Display for GraphMetrics<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Nodes: {}", self.nodes)?;
+        writeln!(f, "Edges: {}", self.edges)?;
+        writeln!(f, "Components: {}", self.components)?;
+        writeln!(f, "Tree: {}", self.is_tree)?;
+        writeln!(f, "Diameter: {}", self.diameter)?;
+        writeln!(f, "Radius: {}", self.radius)?;
+        writeln!(f, "Centre: {:?}", self.centre)?;
+
+        match &self.ends {
+            Some((a, b)) => write!(f, "Furthest apart: {:?} & {:?}", a, b),
+            None => write!(f, "Furthest apart: -"),
+        }
+    }
+}
+
+impl<T> Graph<T>
+where
+    T: PartialEq + Eq + Hash + Clone + Debug + Ord,
+{
+    /// The node furthest from `start` & how far away it is, ties go to the lowest node so the
+    /// result doesn't depend on hash order
+    fn furthest_from(&self, start: T) -> (T, usize, HashMap<T, usize>) {
+        let distances = self.distances(start.clone());
+        let (node, distance) = distances
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(node, distance)| (node.clone(), *distance))
+            .unwrap_or((start, 0));
+
+        (node, distance, distances)
+    }
+
+    /// Distance between every pair of nodes that can reach each other, found with a BFS from
+    /// every node. Takes O(n²) memory, so only meant for small graphs.
+    pub fn all_pairs_distances(&self) -> HashMap<T, HashMap<T, usize>> {
+        self.nodes()
+            .iter()
+            .map(|node| (node.clone(), self.distances(node.clone())))
+            .collect()
+    }
+
+    /// Whether the graph is an undirected tree, the only graphs the double BFS shortcuts work on
+    fn is_undirected_tree(&self) -> bool {
+        !self.is_directed() && self.is_perfect()
+    }
+
+    /// Distance from each node to the node furthest from it, only counting nodes it can reach.
+    /// On an undirected tree this takes three BFS passes, as every node is furthest from one of
+    /// the ends of the diameter, otherwise it's a BFS from every node.
+    pub fn eccentricities(&self) -> HashMap<T, usize> {
+        let ends = match self.is_undirected_tree() {
+            true => self.diameter_of(true).map(|(a, b, _)| (a, b)),
+            false => None,
+        };
+
+        self.eccentricities_of(ends)
+    }
+
+    /// `eccentricities` given the ends of the diameter when the graph is an undirected tree
+    fn eccentricities_of(&self, tree_ends: Option<(T, T)>) -> HashMap<T, usize> {
+        if let Some((a, b)) = tree_ends {
+            let from_a = self.distances(a);
+            let from_b = self.distances(b);
+
+            return from_a
+                .into_iter()
+                .map(|(node, distance)| {
+                    let other = from_b[&node];
+                    (node, distance.max(other))
+                })
+                .collect();
+        }
+
+        self.nodes()
+            .iter()
+            .map(|node| (node.clone(), self.furthest_from(node.clone()).1))
+            .collect()
+    }
+
+    /// Two nodes furthest apart & the distance between them, `None` for an empty graph. An
+    /// undirected tree uses the double BFS method, the node furthest from any start is one end of
+    /// the diameter and the node furthest from that is the other. Graphs with loops or one way
+    /// edges fall back to a BFS from every node.
+    pub fn diameter(&self) -> Option<(T, T, usize)> {
+        self.diameter_of(self.is_undirected_tree())
+    }
+
+    /// `diameter` once it's known whether the graph is an undirected tree
+    fn diameter_of(&self, tree: bool) -> Option<(T, T, usize)> {
+        let first = self.nodes().first()?.clone();
+
+        if tree {
+            let (a, _, _) = self.furthest_from(first);
+            let (b, distance, _) = self.furthest_from(a.clone());
+
+            return Some((a, b, distance));
+        }
+
+        self.nodes()
+            .iter()
+            .cloned()
+            .map(|node| {
+                let (furthest, distance, _) = self.furthest_from(node.clone());
+                (node, furthest, distance)
+            })
+            .max_by(|a, b| a.2.cmp(&b.2).then_with(|| b.0.cmp(&a.0)))
+    }
+
+    /// Lowest eccentricity of any node, `None` for an empty graph
+    pub fn radius(&self) -> Option<usize> {
+        self.eccentricities().into_values().min()
+    }
+
+    /// Nodes with the lowest eccentricity in order, the best places to start from to reach
+    /// everywhere quickly
+    pub fn centre(&self) -> Vec<T> {
+        Self::centre_of(&self.eccentricities())
+    }
+
+    fn centre_of(eccentricities: &HashMap<T, usize>) -> Vec<T> {
+        let radius = eccentricities.values().min();
+        let mut centre = eccentricities
+            .iter()
+            .filter(|(_, e)| Some(*e) == radius)
+            .map(|(node, _)| node.clone())
+            .collect::<Vec<_>>();
+
+        centre.sort();
+        centre
+    }
+
+    /// Every metric at once, finding the components, cycles & diameter only once between them
+    pub fn metrics(&self) -> GraphMetrics<T> {
+        let components = self.connected_components().len();
+        let is_tree = components == 1 && !self.has_cycle();
+        let shortcut = is_tree && !self.is_directed();
+        let diameter = self.diameter_of(shortcut);
+        let eccentricities = self.eccentricities_of(match shortcut {
+            true => diameter.clone().map(|(a, b, _)| (a, b)),
+            false => None,
+        });
+
+        GraphMetrics {
+            nodes: self.len(),
+            edges: self.edge_count(),
+            components,
+            is_tree,
+            diameter: diameter.as_ref().map(|d| d.2).unwrap_or(0),
+            radius: eccentricities.values().min().copied().unwrap_or(0),
+            centre: Self::centre_of(&eccentricities),
+            ends: diameter.map(|(a, b, _)| (a, b)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{grid::Grid, maze::MazeGenerate, RandomisedDFS};
+    use crate::graph::builder::GraphBuilder;
+
+    #[test]
+    fn test_metrics_of_path_and_loop() {
+        let path = GraphBuilder::from_edges(vec![(1, 2), (2, 3), (3, 4), (4, 5)]).build();
+        let metrics = path.metrics();
+
+        assert!(metrics.is_tree);
+        assert_eq!((4, 2), (metrics.diameter, metrics.radius));
+        assert_eq!(vec![3], metrics.centre);
+        // Double BFS from 1 finds 5 first, then heads back to 1
+        assert_eq!(Some((5, 1)), metrics.ends);
+
+        // Closing the loop halves the distances and every node is in the centre
+        let ring = GraphBuilder::from_edges(vec![(1, 2), (2, 3), (3, 4), (4, 5), (5, 1)]).build();
+        let metrics = ring.metrics();
+
+        assert!(!metrics.is_tree);
+        assert_eq!((2, 2), (metrics.diameter, metrics.radius));
+        assert_eq!(vec![1, 2, 3, 4, 5], metrics.centre);
+    }
+
+    #[test]
+    fn test_double_bfs_matches_all_pairs() {
+        let mut maze = RandomisedDFS::from_grid_size(12, 12);
+        maze.generate();
+
+        for braided in [false, true] {
+            let mut grid: Grid = maze.grid.clone();
+
+            if braided {
+                grid.braid(1.0);
+            }
+
+            let graph = GraphBuilder::<(usize, usize)>::from(&grid).build();
+            let all_pairs = graph.all_pairs_distances();
+            let expected = all_pairs
+                .iter()
+                .map(|(node, distances)| (*node, *distances.values().max().unwrap()))
+                .collect::<HashMap<_, _>>();
+
+            assert_eq!(expected, graph.eccentricities());
+
+            let (a, b, diameter) = graph.diameter().unwrap();
+            assert_eq!(expected.values().max(), Some(&diameter));
+            assert_eq!(diameter, all_pairs[&a][&b]);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod algo;
+pub mod graph;
+pub mod render;
+pub mod util;
@@ -1,24 +1,25 @@
-mod algo;
-mod graph;
-mod util;
-
 use std::collections::HashMap;
 
-use algo::maze::MazeGenerate;
-use graph::graph::Node;
 use image::{GenericImageView, ImageBuffer, Luma};
-use util::build_offset_getter;
+use maze_v1::algo::{
+    self,
+    grid::{Grid, Topology, SEAM_COLOUR},
+    maze::MazeGenerate,
+};
+use maze_v1::graph::graph::Node;
+use maze_v1::util::build_topology_offset_getter;
 
 type Point = (u32, u32);
 type Path = HashMap<Point, Node<Point>>;
 type MazeImage = ImageBuffer<Luma<u8>, Vec<u8>>;
 type Floors = [bool; 4];
 
-use crate::{algo::maze::Direction, graph::builder::GraphBuilder};
+use maze_v1::{algo::maze::Direction, graph::builder::GraphBuilder};
 
 const SOLUTION_PATH_COLOUR: [u8; 1] = [100u8];
 const PATH_COLOUR: [u8; 1] = [255u8];
 const MAZE_SIZE: (usize, usize) = (100, 100);
+const TOPOLOGY: Topology = Topology::Plane;
 const STARTING_SPOT: Point = (1, 0);
 const ENDING_SPOT: Point = ((MAZE_SIZE.0 as u32 * 2) - 1, MAZE_SIZE.1 as u32 * 2);
 
@@ -33,31 +34,53 @@ fn find_in_path(path: &Path, value: Point) -> Option<&Node<Point>> {
     path.get(&value)
 }
 
-fn draw_solution(image: &mut MazeImage, path: &Path, start: Point) {
+/// Walk the straight corridor between two connected nodes, this can cross a wrapped edge so the
+/// pixels can't be found with a simple range
+fn find_corridor(
+    pixel_map: &HashMap<Point, bool>,
+    offset_getter: &dyn Fn(u32, u32, Direction) -> Option<Point>,
+    from: Point,
+    to: Point,
+) -> Vec<Point> {
+    for direction in Direction::ALL {
+        let mut corridor = Vec::<Point>::new();
+        let mut offset_cell = offset_getter(from.0, from.1, direction);
+
+        while let Some(cell) = offset_cell {
+            if cell == to {
+                return corridor;
+            }
+
+            if !pixel_map.get(&cell).unwrap_or(&false) || cell == from {
+                break;
+            }
+
+            corridor.push(cell);
+            offset_cell = offset_getter(cell.0, cell.1, direction);
+        }
+    }
+
+    vec![]
+}
+
+fn draw_solution(
+    image: &mut MazeImage,
+    path: &Path,
+    pixel_map: &HashMap<Point, bool>,
+    offset_getter: &dyn Fn(u32, u32, Direction) -> Option<Point>,
+    start: Point,
+) {
     let mut next = find_in_path(path, start);
 
     while let Some(node) = next {
-        let mut pixel = image.get_pixel_mut(node.value.0, node.value.1);
+        let pixel = image.get_pixel_mut(node.value.0, node.value.1);
         pixel.0 = SOLUTION_PATH_COLOUR;
 
         match node.parent {
             Some(value) => {
-                if value.0 != node.value.0 {
-                    let x_range = value.0.min(node.value.0)..value.0.max(node.value.0);
-
-                    for x in x_range {
-                        let mut pixel = image.get_pixel_mut(x, node.value.1);
-                        pixel.0 = SOLUTION_PATH_COLOUR;
-                    }
-                }
-
-                if value.1 != node.value.1 {
-                    let y_range = value.1.min(node.value.1)..value.1.max(node.value.1);
-
-                    for y in y_range {
-                        let mut pixel = image.get_pixel_mut(node.value.0, y);
-                        pixel.0 = SOLUTION_PATH_COLOUR;
-                    }
+                for (x, y) in find_corridor(pixel_map, offset_getter, node.value, value) {
+                    let pixel = image.get_pixel_mut(x, y);
+                    pixel.0 = SOLUTION_PATH_COLOUR;
                 }
 
                 next = find_in_path(path, value);
@@ -68,12 +91,12 @@ fn draw_solution(image: &mut MazeImage, path: &Path, start: Point) {
 }
 
 fn add_maze_start(image: &mut MazeImage) {
-    let mut pixel = image.get_pixel_mut(STARTING_SPOT.0, STARTING_SPOT.1);
+    let pixel = image.get_pixel_mut(STARTING_SPOT.0, STARTING_SPOT.1);
     pixel.0 = PATH_COLOUR;
 }
 
 fn add_maze_end(image: &mut MazeImage) {
-    let mut pixel = image.get_pixel_mut(ENDING_SPOT.0, ENDING_SPOT.1);
+    let pixel = image.get_pixel_mut(ENDING_SPOT.0, ENDING_SPOT.1);
     pixel.0 = PATH_COLOUR;
 }
 
@@ -86,14 +109,17 @@ fn find_neighboring_nodes(
     direction: Direction,
 ) {
     let mut offset_cell = offset_getter(x, y, direction);
+    // Edges are weighted by the length of the corridor between the two nodes in pixels
+    let mut length = 0;
 
     while let Some(cell) = offset_cell {
         offset_cell = offset_getter(cell.0, cell.1, direction);
+        length += 1;
 
         if !pixel_map.get(&(cell.0, cell.1)).unwrap_or(&false) {
             offset_cell = None;
         } else if builder.vertices.contains_key(&(cell.0, cell.1)) {
-            builder.add_edge((cell.0, cell.1), (x, y));
+            builder.add_weighted_edge((cell.0, cell.1), (x, y), length);
             offset_cell = None;
         }
     }
@@ -106,41 +132,51 @@ fn get_surrounding_floors(
     y: u32,
 ) -> Floors {
     let floors = [
-        pixel_map
+        *pixel_map
             .get(&offset_getter(x, y, Direction::Top).unwrap_or((0, 0)))
-            .unwrap_or(&false)
-            .clone(),
-        pixel_map
+            .unwrap_or(&false),
+        *pixel_map
             .get(&offset_getter(x, y, Direction::Right).unwrap_or((0, 0)))
-            .unwrap_or(&false)
-            .clone(),
-        pixel_map
+            .unwrap_or(&false),
+        *pixel_map
             .get(&offset_getter(x, y, Direction::Bottom).unwrap_or((0, 0)))
-            .unwrap_or(&false)
-            .clone(),
-        pixel_map
+            .unwrap_or(&false),
+        *pixel_map
             .get(&offset_getter(x, y, Direction::Left).unwrap_or((0, 0)))
-            .unwrap_or(&false)
-            .clone(),
+            .unwrap_or(&false),
     ];
 
     floors
 }
 
-fn manhattan_distance(p1: &Point, p2: &Point) -> i32 {
-    ((p1.0 as i32 - p2.0 as i32) + (p1.1 as i32 - p2.1 as i32)).abs()
-}
-
-fn distance_fn(_: &Point, _: &Point) -> i32 {
-    1
+fn manhattan_distance(p1: &Point, p2: &Point, size: Point) -> usize {
+    TOPOLOGY.manhattan_distance(
+        (p1.0 as usize, p1.1 as usize),
+        (p2.0 as usize, p2.1 as usize),
+        (size.0 as usize, size.1 as usize),
+    )
 }
 
 fn main() {
-    let mut maze_algo = algo::RandomisedDFS::from_grid_size(MAZE_SIZE.0, MAZE_SIZE.1);
+    let grid = Grid::with_topology(MAZE_SIZE.0, MAZE_SIZE.1, TOPOLOGY);
+    let mut maze_algo = algo::RandomisedDFS::from_grid(grid);
     maze_algo.generate();
 
     println!("Maze generated");
 
+    if let Err(errors) = maze_algo.grid.validate() {
+        for error in errors {
+            println!("Invalid maze: {}", error);
+        }
+    }
+
+    println!(
+        "{}",
+        GraphBuilder::<(usize, usize)>::from(&maze_algo.grid)
+            .build()
+            .metrics()
+    );
+
     let image = maze_algo.grid.generate_as_image();
 
     image.save("maze.png").unwrap();
@@ -151,7 +187,7 @@ fn main() {
     println!("Maze image open");
 
     let di = image.dimensions();
-    let offset_getter = build_offset_getter((0, 0), (di.0, di.1));
+    let offset_getter = build_topology_offset_getter((0, 0), (di.0, di.1), TOPOLOGY);
     let image = image.as_mut_luma8().unwrap();
     let mut builder = GraphBuilder::<Point>::new();
 
@@ -161,41 +197,48 @@ fn main() {
     let mut pixel_map = HashMap::<Point, bool>::new();
 
     for (x, y, pixel) in image.enumerate_pixels_mut() {
-        if pixel.0 == PATH_COLOUR || pixel.0 == SOLUTION_PATH_COLOUR {
+        if pixel.0 == PATH_COLOUR || pixel.0 == SOLUTION_PATH_COLOUR || pixel.0 == SEAM_COLOUR {
             pixel_map.insert((x, y), true);
             pixel.0 = PATH_COLOUR;
         }
     }
 
+    let mut nodes = Vec::<(Point, Floors)>::new();
+
     for (x, y, pixel) in image.enumerate_pixels_mut() {
         if pixel.0 == PATH_COLOUR {
             let floors = get_surrounding_floors(&pixel_map, &offset_getter, x, y);
 
             if !is_corridor(floors) {
                 builder.add_node((x, y));
+                nodes.push(((x, y), floors));
+            }
+        }
+    }
 
-                if floors[0] {
-                    find_neighboring_nodes(
-                        &mut builder,
-                        &pixel_map,
-                        &offset_getter,
-                        x,
-                        y,
-                        Direction::Top,
-                    );
-                }
+    // Edges are searched once every node exists, a wrapping topology can lead
+    // to nodes that haven't been visited yet by the loop above
+    for ((x, y), floors) in nodes {
+        if floors[0] {
+            find_neighboring_nodes(
+                &mut builder,
+                &pixel_map,
+                &offset_getter,
+                x,
+                y,
+                Direction::Top,
+            );
+        }
 
-                if floors[3] {
-                    find_neighboring_nodes(
-                        &mut builder,
-                        &pixel_map,
-                        &offset_getter,
-                        x,
-                        y,
-                        Direction::Left,
-                    );
-                }
-            }
+        if floors[3] {
+            find_neighboring_nodes(
+                &mut builder,
+                &pixel_map,
+                &offset_getter,
+                x,
+                y,
+                Direction::Left,
+            );
         }
     }
 
@@ -203,8 +246,13 @@ fn main() {
 
     let graph = builder.build();
 
-    println!("Number of nodes: {}", graph.vertices.len());
-    println!("Has end: {:?}", graph.vertices.contains_key(&ENDING_SPOT));
+    println!("Number of nodes: {}", graph.len());
+    println!("Has end: {:?}", graph.contains(&ENDING_SPOT));
+    println!(
+        "Connected: {}, perfect: {}",
+        graph.connected_components().len() == 1,
+        graph.is_perfect()
+    );
 
     // let path = &graph.bfs(STARTING_SPOT, ENDING_SPOT);
 
@@ -212,21 +260,20 @@ fn main() {
     //     println!("Path found - drawing solution");
     //     println!("Path length: {}", path.len());
 
-    //     draw_solution(image, &path, ENDING_SPOT);
+    //     draw_solution(image, &path, &pixel_map, &offset_getter, ENDING_SPOT);
     // }
 
-    graph.astar(
-        STARTING_SPOT,
-        ENDING_SPOT,
-        &manhattan_distance,
-        &distance_fn,
-    );
+    graph.record_trace();
+    graph.astar(STARTING_SPOT, ENDING_SPOT, &|p1, p2| {
+        manhattan_distance(p1, p2, di)
+    });
     let path = graph.get_path();
 
+    println!("Nodes Checked: {}", graph.get_trace().len());
     println!("Path found - drawing solution");
     println!("Path length: {}", path.len());
 
-    draw_solution(image, &path, ENDING_SPOT);
+    draw_solution(image, &path, &pixel_map, &offset_getter, ENDING_SPOT);
 
     image.save("maze.png").unwrap();
 
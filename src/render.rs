@@ -0,0 +1,53 @@
+pub mod animation;
+pub mod colour;
+pub mod stream;
+pub mod svg;
+pub mod text;
+
+use crate::algo::grid::Grid;
+use crate::algo::maze::Direction;
+
+/// Options shared by the renderers
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Width & height of each cell
+    pub cell_size: u32,
+    /// Thickness of the walls, used as the stroke width for vector output
+    pub wall_width: u32,
+    /// Cell to mark as the way into the maze
+    pub entrance: Option<(usize, usize)>,
+    /// Cell to mark as the way out of the maze
+    pub exit: Option<(usize, usize)>,
+    /// Cells of the solution in order, drawn as a line through the middle of each cell
+    pub solution: Option<Vec<(usize, usize)>>,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 10,
+            wall_width: 2,
+            entrance: None,
+            exit: None,
+            solution: None,
+        }
+    }
+}
+
+/// Whether there is a wall on the horizontal line `line` (0 to `rows`) below column `col`.
+/// Line 0 is the top edge of the maze and line `rows` the bottom edge.
+pub(crate) fn horizontal_wall(grid: &Grid, line: usize, col: usize) -> bool {
+    match line {
+        line if line == grid.rows => grid.has_wall(col, line - 1, Direction::Bottom),
+        line => grid.has_wall(col, line, Direction::Top),
+    }
+}
+
+/// Whether there is a wall on the vertical line `line` (0 to `cols`) beside row `row`.
+/// Line 0 is the left edge of the maze and line `cols` the right edge.
+pub(crate) fn vertical_wall(grid: &Grid, line: usize, row: usize) -> bool {
+    match line {
+        line if line == grid.cols => grid.has_wall(line - 1, row, Direction::Right),
+        line => grid.has_wall(line, row, Direction::Left),
+    }
+}
@@ -0,0 +1,201 @@
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageResult, Rgba};
+use std::io::Write;
+
+use super::colour::ColourOptions;
+use super::RenderOptions;
+use crate::algo::grid::Grid;
+use crate::algo::maze::{Direction, MazeGrid};
+
+#[derive(Debug, Clone)]
+pub struct AnimationOptions {
+    pub render: RenderOptions,
+    pub colours: ColourOptions,
+    /// Number of events shown in each frame, bigger strides make shorter animations
+    pub stride: usize,
+    /// How long each frame is shown for in milliseconds
+    pub delay_ms: u32,
+    /// How long the final frame is shown for before the animation loops
+    pub hold_ms: u32,
+    /// Cells the generator hasn't reached yet
+    pub unvisited: Rgba<u8>,
+    /// Cells a search has expanded
+    pub visited: Rgba<u8>,
+    /// The cell of the latest event in the frame
+    pub active: Rgba<u8>,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            render: RenderOptions {
+                cell_size: 6,
+                wall_width: 2,
+                ..RenderOptions::default()
+            },
+            colours: ColourOptions::default(),
+            stride: 1,
+            delay_ms: 40,
+            hold_ms: 2000,
+            unvisited: Rgba([60, 60, 60, 255]),
+            visited: Rgba([150, 200, 255, 255]),
+            active: Rgba([255, 140, 0, 255]),
+        }
+    }
+}
+
+impl AnimationOptions {
+    fn frame(
+        &self,
+        grid: &Grid,
+        render: &RenderOptions,
+        colours: &ColourOptions,
+        last: bool,
+    ) -> Frame {
+        let delay = match last {
+            true => self.hold_ms,
+            false => self.delay_ms,
+        };
+
+        Frame::from_parts(
+            grid.generate_as_colour_image(render, colours),
+            0,
+            0,
+            Delay::from_numer_denom_ms(delay, 1),
+        )
+    }
+}
+
+/// Replay the walls removed by a generator, e.g. from `RecordedGrid::carves`, onto `start` which
+/// should be the grid as it was before generating
+pub fn generation_frames(
+    start: &Grid,
+    carves: &[(usize, Direction)],
+    options: &AnimationOptions,
+) -> Vec<Frame> {
+    let mut grid = start.clone();
+    let mut frames = Vec::<Frame>::new();
+    let chunks = carves.chunks(options.stride.max(1));
+    let count = chunks.len();
+
+    // Every cell starts unvisited, `reveal` puts back any highlight of its own once it's reached
+    let mut colours = options.colours.clone();
+    let unvisited = grid
+        .cells()
+        .map(|cell| (cell.to_visited_id(), options.unvisited));
+    colours.highlights.extend(unvisited);
+
+    let reveal = |colours: &mut ColourOptions, cell: (usize, usize)| {
+        match options.colours.highlights.get(&cell) {
+            Some(colour) => colours.highlights.insert(cell, *colour),
+            None => colours.highlights.remove(&cell),
+        };
+    };
+
+    frames.push(options.frame(&grid, &options.render, &colours, count == 0));
+
+    // One frame for each chunk of carves after the one for the untouched grid
+    for (idx, chunk) in chunks.enumerate() {
+        let mut active = None;
+
+        for (id, dir) in chunk {
+            let cell = grid.cell_at(*id);
+            grid.carve(*id, *dir);
+            reveal(&mut colours, cell.to_visited_id());
+
+            if let Some(neighbor) = grid.get_neighbor_cell(&cell, *dir) {
+                reveal(&mut colours, neighbor.to_visited_id());
+                active = Some(neighbor.to_visited_id());
+            }
+        }
+
+        let last = idx + 1 == count;
+
+        match (active, last) {
+            (Some(active), false) => {
+                colours.highlights.insert(active, options.active);
+                frames.push(options.frame(&grid, &options.render, &colours, last));
+                reveal(&mut colours, active);
+            }
+            _ => frames.push(options.frame(&grid, &options.render, &colours, last)),
+        }
+    }
+
+    frames
+}
+
+/// Show the cells a search expanded in order, e.g. from `Graph::get_trace`, finishing with the
+/// solution drawn over the top
+pub fn solving_frames(
+    grid: &Grid,
+    trace: &[(usize, usize)],
+    solution: Option<Vec<(usize, usize)>>,
+    options: &AnimationOptions,
+) -> Vec<Frame> {
+    let mut colours = options.colours.clone();
+    let mut frames = Vec::<Frame>::new();
+
+    for chunk in trace.chunks(options.stride.max(1)) {
+        for cell in chunk {
+            colours.highlights.insert(*cell, options.visited);
+        }
+
+        let mut frame_colours = colours.clone();
+
+        if let Some(active) = chunk.last() {
+            frame_colours.highlights.insert(*active, options.active);
+        }
+
+        frames.push(options.frame(grid, &options.render, &frame_colours, false));
+    }
+
+    // Solution is drawn under the highlights, so they're removed from its cells
+    for cell in solution.iter().flatten() {
+        colours.highlights.remove(cell);
+    }
+
+    let render = RenderOptions {
+        solution,
+        ..options.render.clone()
+    };
+
+    frames.push(options.frame(grid, &render, &colours, true));
+
+    frames
+}
+
+/// Encode the frames as a GIF that loops forever
+pub fn write_gif<W: Write>(frames: Vec<Frame>, writer: W) -> ImageResult<()> {
+    let mut encoder = GifEncoder::new_with_speed(writer, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    encoder.encode_frames(frames)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{maze::MazeGenerate, recording::RecordedGrid, RandomisedDFS};
+
+    #[test]
+    fn test_generation_frames_follow_stride() {
+        let mut maze = RandomisedDFS::from_grid(RecordedGrid::new(Grid::new(3, 3)));
+        maze.generate();
+
+        // A perfect 3x3 maze removes 8 walls
+        assert_eq!(8, maze.grid.carves.len());
+
+        let options = AnimationOptions {
+            stride: 3,
+            ..AnimationOptions::default()
+        };
+        let frames = generation_frames(&Grid::new(3, 3), &maze.grid.carves, &options);
+
+        assert_eq!(4, frames.len());
+        // The first frame has nothing carved so every cell is unvisited
+        assert_eq!(options.unvisited, *frames[0].buffer().get_pixel(2, 2));
+
+        let mut gif = Vec::<u8>::new();
+        write_gif(frames, &mut gif).unwrap();
+        assert_eq!(b"GIF89a", &gif[..6]);
+    }
+}
@@ -0,0 +1,331 @@
+use image::{Rgba, RgbaImage};
+use std::collections::HashMap;
+
+use super::RenderOptions;
+use crate::algo::grid::Grid;
+use crate::algo::maze::Direction;
+use crate::graph::builder::GraphBuilder;
+
+/// Colours spaced evenly from 0 to 1, blended together in between
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub stops: Vec<Rgba<u8>>,
+}
+
+impl Gradient {
+    pub fn new(stops: Vec<Rgba<u8>>) -> Self {
+        Self { stops }
+    }
+
+    /// Dark purple through blue & green to yellow, similar to viridis
+    pub fn viridis() -> Self {
+        Gradient::new(vec![
+            Rgba([68, 1, 84, 255]),
+            Rgba([59, 82, 139, 255]),
+            Rgba([33, 145, 140, 255]),
+            Rgba([94, 201, 98, 255]),
+            Rgba([253, 231, 37, 255]),
+        ])
+    }
+
+    /// Black through red & orange to white
+    pub fn heat() -> Self {
+        Gradient::new(vec![
+            Rgba([0, 0, 0, 255]),
+            Rgba([180, 20, 0, 255]),
+            Rgba([255, 160, 0, 255]),
+            Rgba([255, 255, 255, 255]),
+        ])
+    }
+
+    pub fn greyscale() -> Self {
+        Gradient::new(vec![Rgba([0, 0, 0, 255]), Rgba([255, 255, 255, 255])])
+    }
+
+    /// Colour at `t`, clamped between 0 & 1
+    pub fn at(&self, t: f64) -> Rgba<u8> {
+        match self.stops.len() {
+            0 => Rgba([0, 0, 0, 0]),
+            1 => self.stops[0],
+            len => {
+                let position = t.clamp(0.0, 1.0) * (len - 1) as f64;
+                let idx = (position.floor() as usize).min(len - 2);
+
+                mix(self.stops[idx], self.stops[idx + 1], position - idx as f64)
+            }
+        }
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Gradient::viridis()
+    }
+}
+
+/// Blend from `a` to `b`, `t` of 0 is all `a` and 1 is all `b`
+fn mix(a: Rgba<u8>, b: Rgba<u8>, t: f64) -> Rgba<u8> {
+    let channel = |i: usize| (a.0[i] as f64 + (b.0[i] as f64 - a.0[i] as f64) * t).round() as u8;
+
+    Rgba([channel(0), channel(1), channel(2), channel(3)])
+}
+
+#[derive(Debug, Clone)]
+pub struct ColourOptions {
+    pub wall: Rgba<u8>,
+    pub floor: Rgba<u8>,
+    /// Shade each cell with `gradient` by its distance from this cell, the furthest cell is
+    /// the end of the gradient
+    pub distances_from: Option<(usize, usize)>,
+    pub gradient: Gradient,
+    /// Give each group of cells that can reach each other a colour from this gradient, spread
+    /// evenly across it. Drawn under the distance shading, which only covers one region.
+    pub regions: Option<Gradient>,
+    pub solution: Rgba<u8>,
+    /// Colour cells with a single way in or out, drawn over the distance shading
+    pub dead_end: Option<Rgba<u8>>,
+    pub entrance: Rgba<u8>,
+    pub exit: Rgba<u8>,
+    /// Cells drawn in a specific colour, over everything apart from the entrance & exit
+    pub highlights: HashMap<(usize, usize), Rgba<u8>>,
+}
+
+impl Default for ColourOptions {
+    fn default() -> Self {
+        Self {
+            wall: Rgba([0, 0, 0, 255]),
+            floor: Rgba([255, 255, 255, 255]),
+            distances_from: None,
+            gradient: Gradient::default(),
+            regions: None,
+            solution: Rgba([230, 30, 30, 255]),
+            dead_end: None,
+            entrance: Rgba([30, 170, 30, 255]),
+            exit: Rgba([200, 30, 30, 255]),
+            highlights: HashMap::new(),
+        }
+    }
+}
+
+impl Grid {
+    /// Render the maze in colour. Cells are `cell_size` squares with `wall_width` lines of wall
+    /// between them, an open wall is filled with a blend of the cells either side.
+    pub fn generate_as_colour_image(
+        &self,
+        options: &RenderOptions,
+        colours: &ColourOptions,
+    ) -> RgbaImage {
+        let size = options.cell_size;
+        let wall = options.wall_width;
+        let step = size + wall;
+        let image_width = self.cols as u32 * step + wall;
+        let image_height = self.rows as u32 * step + wall;
+
+        let graph = (colours.distances_from.is_some() || colours.regions.is_some())
+            .then(|| GraphBuilder::<(usize, usize)>::from(self).build());
+        let distances = colours
+            .distances_from
+            .zip(graph.as_ref())
+            .map(|(from, graph)| graph.distances(from));
+        let mut region_colours = HashMap::<(usize, usize), Rgba<u8>>::new();
+
+        if let (Some(gradient), Some(graph)) = (&colours.regions, &graph) {
+            let components = graph.connected_components();
+            let last = components.len().saturating_sub(1).max(1);
+
+            for (i, component) in components.into_iter().enumerate() {
+                let colour = gradient.at(i as f64 / last as f64);
+                region_colours.extend(component.into_iter().map(|cell| (cell, colour)));
+            }
+        }
+        let furthest = distances
+            .as_ref()
+            .and_then(|d| d.values().max().copied())
+            .unwrap_or(0)
+            .max(1);
+
+        let mut cell_colours = HashMap::<(usize, usize), Rgba<u8>>::new();
+
+        for cell in self.cells() {
+            let id = cell.to_visited_id();
+            let mut colour = region_colours.get(&id).copied().unwrap_or(colours.floor);
+
+            if let Some(distance) = distances.as_ref().and_then(|d| d.get(&id)) {
+                colour = colours.gradient.at(*distance as f64 / furthest as f64);
+            }
+
+            if let Some(dead_end) = colours.dead_end {
+                if cell.walls.iter().filter(|wall| **wall).count() == 3 {
+                    colour = dead_end;
+                }
+            }
+
+            cell_colours.insert(id, colour);
+        }
+
+        for cell in options.solution.iter().flatten() {
+            cell_colours.insert(*cell, colours.solution);
+        }
+
+        cell_colours.extend(colours.highlights.iter());
+
+        for (cell, colour) in [
+            (options.entrance, colours.entrance),
+            (options.exit, colours.exit),
+        ] {
+            if let Some(cell) = cell {
+                cell_colours.insert(cell, colour);
+            }
+        }
+
+        let mut img = RgbaImage::from_pixel(image_width, image_height, colours.wall);
+
+        let mut fill = |left: u32, top: u32, width: u32, height: u32, colour: Rgba<u8>| {
+            for x in left..left + width {
+                for y in top..top + height {
+                    img.put_pixel(x, y, colour);
+                }
+            }
+        };
+
+        for cell in self.cells() {
+            let colour = cell_colours[&cell.to_visited_id()];
+            let left = wall + cell.x as u32 * step;
+            let top = wall + cell.y as u32 * step;
+
+            fill(left, top, size, size, colour);
+
+            for dir in Direction::ALL {
+                // Gaps on the left & top are filled by the neighbouring cell, apart from seams
+                let inside = match dir {
+                    Direction::Top => cell.y > 0,
+                    Direction::Left => cell.x > 0,
+                    _ => false,
+                };
+
+                if cell.walls[dir.index()] || inside {
+                    continue;
+                }
+
+                let neighbor = self
+                    .get_neighbor_cell(&cell, dir)
+                    .map_or(colour, |n| cell_colours[&n.to_visited_id()]);
+                let gap = mix(colour, neighbor, 0.5);
+
+                match dir {
+                    Direction::Top => fill(left, top - wall, size, wall, gap),
+                    Direction::Right => fill(left + size, top, wall, size, gap),
+                    Direction::Bottom => fill(left, top + size, size, wall, gap),
+                    Direction::Left => fill(left - wall, top, wall, size, gap),
+                }
+            }
+        }
+
+        img
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAZE: &str = "\
++---+---+---+
+|       |   |
++---+   +   +
+|       |   |
++   +---+   +
+|           |
++---+---+---+
+";
+
+    #[test]
+    fn test_gradient_blends_between_stops() {
+        let gradient = Gradient::greyscale();
+
+        assert_eq!(Rgba([0, 0, 0, 255]), gradient.at(-1.0));
+        assert_eq!(Rgba([128, 128, 128, 255]), gradient.at(0.5));
+        assert_eq!(Rgba([255, 255, 255, 255]), gradient.at(1.0));
+    }
+
+    #[test]
+    fn test_cells_shaded_by_distance() {
+        let grid: Grid = MAZE.parse().unwrap();
+        let options = RenderOptions {
+            cell_size: 2,
+            wall_width: 1,
+            ..RenderOptions::default()
+        };
+
+        let img = grid.generate_as_colour_image(
+            &options,
+            &ColourOptions {
+                distances_from: Some((0, 0)),
+                gradient: Gradient::greyscale(),
+                ..ColourOptions::default()
+            },
+        );
+
+        assert_eq!((10, 10), img.dimensions());
+        // (0, 0) is the start & (2, 0) is the furthest cell, 8 steps away
+        assert_eq!(Rgba([0, 0, 0, 255]), *img.get_pixel(1, 1));
+        assert_eq!(Rgba([255, 255, 255, 255]), *img.get_pixel(7, 1));
+        // Open wall between (0, 0) & (1, 0) is a blend of the two
+        assert_eq!(Rgba([16, 16, 16, 255]), *img.get_pixel(3, 1));
+        assert_eq!(ColourOptions::default().wall, *img.get_pixel(6, 1));
+    }
+
+    #[test]
+    fn test_dead_ends_and_solution() {
+        let grid: Grid = MAZE.parse().unwrap();
+        let colours = ColourOptions {
+            dead_end: Some(Rgba([0, 0, 255, 255])),
+            ..ColourOptions::default()
+        };
+
+        let img = grid.generate_as_colour_image(
+            &RenderOptions {
+                cell_size: 2,
+                wall_width: 1,
+                solution: Some(vec![(1, 1), (0, 1)]),
+                ..RenderOptions::default()
+            },
+            &colours,
+        );
+
+        assert_eq!(colours.dead_end.unwrap(), *img.get_pixel(1, 1));
+        assert_eq!(colours.solution, *img.get_pixel(3, 4));
+        assert_eq!(colours.floor, *img.get_pixel(7, 7));
+    }
+
+    #[test]
+    fn test_regions_coloured_apart() {
+        let grid: Grid = "\
++---+---+---+
+|   |       |
++   +---+   +
+|   |       |
++---+---+---+
+"
+        .parse()
+        .unwrap();
+
+        let img = grid.generate_as_colour_image(
+            &RenderOptions {
+                cell_size: 2,
+                wall_width: 1,
+                ..RenderOptions::default()
+            },
+            &ColourOptions {
+                regions: Some(Gradient::greyscale()),
+                ..ColourOptions::default()
+            },
+        );
+
+        // The left column is one region & the other four cells are another
+        assert_eq!(Rgba([0, 0, 0, 255]), *img.get_pixel(1, 1));
+        assert_eq!(Rgba([0, 0, 0, 255]), *img.get_pixel(1, 4));
+        assert_eq!(Rgba([255, 255, 255, 255]), *img.get_pixel(4, 1));
+        assert_eq!(Rgba([255, 255, 255, 255]), *img.get_pixel(7, 4));
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::algo::grid::{Grid, SEAM_COLOUR};
+use crate::algo::maze::Direction;
+
+const WALL: u8 = 0;
+const OPEN: u8 = 255;
+
+impl Grid {
+    /// Width & height in pixels of the image `generate_as_image` draws, each cell and each wall
+    /// is a single pixel
+    pub fn image_size(&self) -> (u32, u32) {
+        (self.cols as u32 * 2 + 1, self.rows as u32 * 2 + 1)
+    }
+
+    /// Fill `row` with the pixels of row `y` of the image `generate_as_image` draws, `row` must
+    /// be as long as the image is wide. Even rows are lines of walls, odd rows go through the
+    /// middle of a row of cells.
+    pub fn image_row(&self, y: u32, row: &mut [u8]) {
+        let y = y as usize;
+
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = match (x % 2, y % 2) {
+                // Corners are always a wall
+                (0, 0) => WALL,
+                (1, 1) => OPEN,
+                // A horizontal wall between the cells above & below
+                (1, 0) => {
+                    let (col, line) = (x / 2, y / 2);
+                    let above = line > 0 && !self.has_wall(col, line - 1, Direction::Bottom);
+                    let below = line < self.rows && !self.has_wall(col, line, Direction::Top);
+
+                    self.opening(above || below, line == 0 || line == self.rows)
+                }
+                // A vertical wall between the cells to the left & right
+                _ => {
+                    let (line, row) = (x / 2, y / 2);
+                    let left = line > 0 && !self.has_wall(line - 1, row, Direction::Right);
+                    let right = line < self.cols && !self.has_wall(line, row, Direction::Left);
+
+                    self.opening(left || right, line == 0 || line == self.cols)
+                }
+            };
+        }
+    }
+
+    /// Colour of a wall pixel, openings on the outside edge can only come from a wrapped topology
+    fn opening(&self, open: bool, edge: bool) -> u8 {
+        match (open, edge) {
+            (false, _) => WALL,
+            (true, true) => SEAM_COLOUR[0],
+            (true, false) => OPEN,
+        }
+    }
+
+    /// Encode the image `generate_as_image` draws as a greyscale PNG one row at a time, so only a
+    /// single row of pixels is held in memory however big the maze is
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let (width, height) = self.image_size();
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut header = encoder.write_header()?;
+        let mut stream = header.stream_writer()?;
+        let mut row = vec![WALL; width as usize];
+
+        for y in 0..height {
+            self.image_row(y, &mut row);
+            stream.write_all(&row)?;
+        }
+
+        stream.finish()?;
+        Ok(())
+    }
+
+    /// Stream the maze to a PNG file at `path`, see `write_png`
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_png(BufWriter::new(File::create(path)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::algo::grid::{Grid, Topology};
+    use crate::algo::{maze::MazeGenerate, RandomisedDFS};
+
+    #[test]
+    fn test_streamed_png_matches_image() {
+        for topology in [Topology::Plane, Topology::Torus, Topology::Mobius] {
+            let mut maze =
+                RandomisedDFS::from_grid(Grid::with_topology(7, 9, topology)).with_seed(3);
+            maze.generate();
+
+            let mut png = Vec::<u8>::new();
+            maze.grid.write_png(&mut png).unwrap();
+
+            let streamed = image::load_from_memory(&png).unwrap().into_luma8();
+            let image = maze.grid.generate_as_image();
+
+            assert_eq!((19, 15), streamed.dimensions());
+            assert!(image == streamed, "{:?} images differ", topology);
+        }
+    }
+}
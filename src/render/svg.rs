@@ -0,0 +1,201 @@
+use std::fmt::Write;
+
+use super::{horizontal_wall, vertical_wall, RenderOptions};
+use crate::algo::grid::Grid;
+
+const WALL_COLOUR: &str = "#000";
+const SOLUTION_COLOUR: &str = "#d33";
+const ENTRANCE_COLOUR: &str = "#2a2";
+const EXIT_COLOUR: &str = "#c22";
+
+/// Merge a line of wall flags into runs of consecutive walls, as `(start, end)` cell offsets
+fn wall_runs(walls: impl Iterator<Item = bool>) -> Vec<(usize, usize)> {
+    let mut runs = Vec::<(usize, usize)>::new();
+    let mut start: Option<usize> = None;
+    let mut len = 0;
+
+    for (idx, wall) in walls.enumerate() {
+        match (wall, start) {
+            (true, None) => start = Some(idx),
+            (false, Some(s)) => {
+                runs.push((s, idx));
+                start = None;
+            }
+            _ => {}
+        }
+
+        len = idx + 1;
+    }
+
+    if let Some(s) = start {
+        runs.push((s, len));
+    }
+
+    runs
+}
+
+/// Split the solution where it crosses a wrapped edge so the line doesn't cut across the maze
+fn solution_segments(solution: &[(usize, usize)]) -> Vec<&[(usize, usize)]> {
+    let mut segments = Vec::<&[(usize, usize)]>::new();
+    let mut start = 0;
+
+    for idx in 1..solution.len() {
+        let (a, b) = (solution[idx - 1], solution[idx]);
+
+        if a.0.abs_diff(b.0) + a.1.abs_diff(b.1) > 1 {
+            segments.push(&solution[start..idx]);
+            start = idx;
+        }
+    }
+
+    if start < solution.len() {
+        segments.push(&solution[start..]);
+    }
+
+    segments
+}
+
+impl Grid {
+    /// Render the maze as an SVG document. Walls are merged into long runs so each line of walls
+    /// is only a handful of path segments, keeping the file small for big mazes.
+    pub fn generate_as_svg(&self, options: &RenderOptions) -> String {
+        let size = options.cell_size as usize;
+        // Leave room for the stroke of the outer walls
+        let pad = options.wall_width as usize;
+        let width = self.cols * size + pad * 2;
+        let height = self.rows * size + pad * 2;
+        let centre = |cell: (usize, usize)| {
+            (
+                pad + cell.0 * size + size / 2,
+                pad + cell.1 * size + size / 2,
+            )
+        };
+
+        let mut svg = String::new();
+        let mut walls = String::new();
+
+        // Horizontal walls, the line above each row plus the bottom edge
+        for y in 0..=self.rows {
+            let line = (0..self.cols).map(|x| horizontal_wall(self, y, x));
+
+            for (start, end) in wall_runs(line) {
+                let _ = write!(
+                    walls,
+                    "M{} {}H{}",
+                    pad + start * size,
+                    pad + y * size,
+                    pad + end * size
+                );
+            }
+        }
+
+        // Vertical walls, the line left of each column plus the right edge
+        for x in 0..=self.cols {
+            let line = (0..self.rows).map(|y| vertical_wall(self, x, y));
+
+            for (start, end) in wall_runs(line) {
+                let _ = write!(
+                    walls,
+                    "M{} {}V{}",
+                    pad + x * size,
+                    pad + start * size,
+                    pad + end * size
+                );
+            }
+        }
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = width,
+            h = height
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+        let _ = writeln!(
+            svg,
+            r#"<path d="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="square"/>"#,
+            walls, WALL_COLOUR, options.wall_width
+        );
+
+        if let Some(solution) = &options.solution {
+            for segment in solution_segments(solution) {
+                let points = segment
+                    .iter()
+                    .map(|cell| {
+                        let (x, y) = centre(*cell);
+                        format!("{},{}", x, y)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                let _ = writeln!(
+                    svg,
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                    points,
+                    SOLUTION_COLOUR,
+                    (size / 4).max(1)
+                );
+            }
+        }
+
+        for (cell, colour) in [
+            (options.entrance, ENTRANCE_COLOUR),
+            (options.exit, EXIT_COLOUR),
+        ] {
+            if let Some(cell) = cell {
+                let (x, y) = centre(cell);
+                let _ = writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    x,
+                    y,
+                    (size / 3).max(1),
+                    colour
+                );
+            }
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::algo::{
+        grid::Topology,
+        maze::{Direction, MazeGrid},
+    };
+
+    #[test]
+    fn test_walls_merge_into_runs() {
+        let mut grid = Grid::new(2, 3);
+        // Open the wall between (0, 0) & (1, 0)
+        grid.carve(0, Direction::Right);
+
+        let svg = grid.generate_as_svg(&RenderOptions {
+            cell_size: 10,
+            wall_width: 2,
+            ..RenderOptions::default()
+        });
+
+        assert!(svg.contains(r#"d="M2 2H32M2 12H32M2 22H32M2 2V22M12 12V22M22 2V22M32 2V22""#));
+    }
+
+    #[test]
+    fn test_solution_splits_across_seam() {
+        let grid = Grid::with_topology(1, 4, Topology::Cylinder);
+
+        let svg = grid.generate_as_svg(&RenderOptions {
+            entrance: Some((1, 0)),
+            exit: Some((2, 0)),
+            solution: Some(vec![(1, 0), (0, 0), (3, 0), (2, 0)]),
+            ..RenderOptions::default()
+        });
+
+        assert_eq!(2, svg.matches("<polyline").count());
+        assert!(svg.contains(r#"points="17,7 7,7""#));
+        assert_eq!(2, svg.matches("<circle").count());
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use super::{horizontal_wall, vertical_wall};
+use crate::algo::grid::Grid;
+use crate::algo::maze::Direction;
+
+/// Marks a cell or gap in a wall that is part of the solution
+const SOLUTION_MARK: char = '*';
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum TextStyle {
+    /// `+---+` style using plain ASCII characters
+    #[default]
+    Ascii,
+    /// Unicode box-drawing characters with the right glyph at each junction
+    Unicode,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextOptions {
+    pub style: TextStyle,
+    /// Cells of the solution in order, marked with a `*` in each cell and the gaps between them
+    pub solution: Option<Vec<(usize, usize)>>,
+    /// Text to show in a cell, only the first 3 characters fit. Labels are drawn over the solution.
+    pub labels: HashMap<(usize, usize), String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseGridError {
+    /// There wasn't enough text to contain a single cell
+    TooSmall,
+    /// A line doesn't have the right number of characters for the grid
+    BadLineLength { line: usize, expected: usize },
+    /// The number of lines doesn't fit a row of cells plus a line of walls below each
+    BadLineCount(usize),
+}
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseGridError::TooSmall => write!(f, "text is too small to contain a maze"),
+            ParseGridError::BadLineLength { line, expected } => {
+                write!(f, "line {} should be {} characters long", line, expected)
+            }
+            ParseGridError::BadLineCount(count) => {
+                write!(f, "{} lines can't hold whole rows of cells", count)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseGridError {}
+
+/// The glyph for a junction of walls, based on which of the four arms have a wall
+fn junction(style: TextStyle, up: bool, right: bool, down: bool, left: bool) -> char {
+    if style == TextStyle::Ascii {
+        return '+';
+    }
+
+    match (up, right, down, left) {
+        (false, false, false, false) => ' ',
+        (true, false, false, false) => '╵',
+        (false, true, false, false) => '╶',
+        (false, false, true, false) => '╷',
+        (false, false, false, true) => '╴',
+        (true, false, true, false) => '│',
+        (false, true, false, true) => '─',
+        (false, true, true, false) => '┌',
+        (false, false, true, true) => '┐',
+        (true, true, false, false) => '└',
+        (true, false, false, true) => '┘',
+        (true, true, true, false) => '├',
+        (true, false, true, true) => '┤',
+        (false, true, true, true) => '┬',
+        (true, true, false, true) => '┴',
+        (true, true, true, true) => '┼',
+    }
+}
+
+fn is_horizontal_wall(c: char) -> bool {
+    matches!(c, '-' | '─')
+}
+
+fn is_vertical_wall(c: char) -> bool {
+    matches!(c, '|' | '│')
+}
+
+impl Grid {
+    /// Render the maze as text, each cell is 3 characters wide with a line of walls between rows
+    pub fn generate_as_text(&self, options: &TextOptions) -> String {
+        let style = options.style;
+        let (h_wall, v_wall) = match style {
+            TextStyle::Ascii => ("---", '|'),
+            TextStyle::Unicode => ("───", '│'),
+        };
+
+        let solution = options.solution.as_deref().unwrap_or_default();
+        let on_solution: HashSet<&(usize, usize)> = solution.iter().collect();
+        // Gaps between neighbouring solution cells, stored as (cell, direction of the gap)
+        let mut solution_gaps = HashSet::<((usize, usize), Direction)>::new();
+
+        for pair in solution.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+
+            let gap = match (b.0 as isize - a.0 as isize, b.1 as isize - a.1 as isize) {
+                (1, 0) => (a, Direction::Right),
+                (-1, 0) => (b, Direction::Right),
+                (0, 1) => (a, Direction::Bottom),
+                (0, -1) => (b, Direction::Bottom),
+                // Crossed a wrapped edge, there is no gap to draw
+                _ => continue,
+            };
+
+            solution_gaps.insert(gap);
+        }
+
+        let mut text = String::new();
+
+        for line in 0..=self.rows {
+            // Line of walls above row `line`
+            for x in 0..=self.cols {
+                let up = line > 0 && vertical_wall(self, x, line - 1);
+                let down = line < self.rows && vertical_wall(self, x, line);
+                let left = x > 0 && horizontal_wall(self, line, x - 1);
+                let right = x < self.cols && horizontal_wall(self, line, x);
+
+                text.push(junction(style, up, right, down, left));
+
+                if x == self.cols {
+                    break;
+                }
+
+                if right {
+                    text.push_str(h_wall);
+                } else if line > 0 && solution_gaps.contains(&((x, line - 1), Direction::Bottom)) {
+                    text.push(' ');
+                    text.push(SOLUTION_MARK);
+                    text.push(' ');
+                } else {
+                    text.push_str("   ");
+                }
+            }
+
+            text.push('\n');
+
+            if line == self.rows {
+                break;
+            }
+
+            // Row of cells
+            for x in 0..=self.cols {
+                let wall = vertical_wall(self, x, line);
+
+                text.push(match wall {
+                    true => v_wall,
+                    false
+                        if x > 0 && solution_gaps.contains(&((x - 1, line), Direction::Right)) =>
+                    {
+                        SOLUTION_MARK
+                    }
+                    false => ' ',
+                });
+
+                if x == self.cols {
+                    break;
+                }
+
+                match options.labels.get(&(x, line)) {
+                    Some(label) => {
+                        let label: String = label.chars().take(3).collect();
+                        text.push_str(&format!("{:^3}", label));
+                    }
+                    None if on_solution.contains(&(x, line)) => {
+                        text.push(' ');
+                        text.push(SOLUTION_MARK);
+                        text.push(' ');
+                    }
+                    None => text.push_str("   "),
+                }
+            }
+
+            text.push('\n');
+        }
+
+        text
+    }
+}
+
+/// Plain text version of the maze, `{:#}` uses Unicode box-drawing characters
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = match f.alternate() {
+            true => TextStyle::Unicode,
+            false => TextStyle::Ascii,
+        };
+
+        let text = self.generate_as_text(&TextOptions {
+            style,
+            ..TextOptions::default()
+        });
+
+        f.write_str(&text)
+    }
+}
+
+/// Parse a maze rendered by `Grid::generate_as_text` in either style. Solutions & labels are
+/// ignored, as is the topology so the grid is always a `Topology::Plane`.
+impl FromStr for Grid {
+    type Err = ParseGridError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Blank lines in the middle can be a line of missing walls, so only the ends are trimmed
+        let mut lines: Vec<Vec<char>> = s
+            .lines()
+            .skip_while(|line| line.is_empty())
+            .map(|line| line.chars().collect())
+            .collect();
+
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+
+        if lines.len() < 3 || lines[0].len() < 5 {
+            return Err(ParseGridError::TooSmall);
+        }
+
+        if lines.len().is_multiple_of(2) {
+            return Err(ParseGridError::BadLineCount(lines.len()));
+        }
+
+        let expected = lines[0].len();
+
+        if !(expected - 1).is_multiple_of(4) {
+            return Err(ParseGridError::BadLineLength {
+                line: 0,
+                expected: expected - (expected - 1) % 4,
+            });
+        }
+
+        if let Some(line) = lines.iter().position(|line| line.len() != expected) {
+            return Err(ParseGridError::BadLineLength { line, expected });
+        }
+
+        let rows = (lines.len() - 1) / 2;
+        let cols = (expected - 1) / 4;
+        let mut grid = Grid::new(rows, cols);
+
+        for y in 0..rows {
+            for x in 0..cols {
+                let slot = 4 * x + 1..4 * x + 4;
+
+                let walls = [
+                    lines[2 * y][slot.clone()]
+                        .iter()
+                        .any(|c| is_horizontal_wall(*c)),
+                    is_vertical_wall(lines[2 * y + 1][4 * x + 4]),
+                    lines[2 * y + 2][slot]
+                        .iter()
+                        .any(|c| is_horizontal_wall(*c)),
+                    is_vertical_wall(lines[2 * y + 1][4 * x]),
+                ];
+
+                for dir in Direction::ALL {
+                    grid.set_wall(x, y, dir, walls[dir.index()]);
+                }
+            }
+        }
+
+        Ok(grid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MAZE: &str = "\
++---+---+---+
+|       |   |
++---+   +   +
+|       |   |
++   +---+   +
+|           |
++---+---+---+
+";
+
+    #[test]
+    fn test_ascii_round_trip() {
+        let grid: Grid = MAZE.parse().unwrap();
+
+        assert_eq!(3, grid.rows);
+        assert_eq!(3, grid.cols);
+        assert!(!grid.has_wall(0, 0, Direction::Right));
+        assert!(grid.has_wall(1, 0, Direction::Right));
+        assert_eq!(MAZE, grid.to_string());
+    }
+
+    #[test]
+    fn test_unicode_junctions_and_overlays() {
+        let grid: Grid = MAZE.parse().unwrap();
+
+        let text = grid.generate_as_text(&TextOptions {
+            style: TextStyle::Unicode,
+            solution: Some(vec![(0, 0), (1, 0), (1, 1), (0, 1), (0, 2)]),
+            labels: HashMap::from([((2, 2), "end".to_string())]),
+        });
+
+        assert_eq!(
+            "\
+┌───────┬───┐
+│ * * * │   │
+├───╴ * │   │
+│ * * * │   │
+│ * ╶───┘   │
+│ *      end│
+└───────────┘
+",
+            text
+        );
+
+        let parsed: Grid = text.parse().unwrap();
+        assert_eq!(MAZE, parsed.to_string());
+        assert_eq!(format!("{:#}", grid), format!("{:#}", parsed));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Err(ParseGridError::TooSmall),
+            "+---+".parse::<Grid>().map(|_| ())
+        );
+        assert_eq!(
+            Err(ParseGridError::BadLineLength {
+                line: 1,
+                expected: 5
+            }),
+            "+---+\n|  |\n+---+".parse::<Grid>().map(|_| ())
+        );
+    }
+}
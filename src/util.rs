@@ -1,37 +1,199 @@
+use crate::algo::grid::Topology;
 use crate::algo::maze::Direction;
+use rand::{rngs::StdRng, SeedableRng};
 use std::ops::{Add, Sub};
 
-pub fn build_offset_getter<T: PartialEq + Sub<u32, Output = T> + Add<u32, Output = T> + Copy>(
+/// Random number generator for a generator, the same `seed` always gives the same sequence while
+/// `None` seeds it from the OS
+pub fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// Union-find over the indexes `0..size`, used to track which cells have already been joined
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    rank: Vec<u8>,
+}
+
+impl DisjointSet {
+    pub fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+            rank: vec![0; size],
+        }
+    }
+
+    pub fn find(&mut self, mut index: usize) -> usize {
+        while self.parent[index] != index {
+            // Path halving, point every other node at its grandparent on the way up
+            self.parent[index] = self.parent[self.parent[index]];
+            index = self.parent[index];
+        }
+
+        index
+    }
+
+    pub fn is_joined(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Join the sets `a` and `b` belong to, returns `false` if they were already joined
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+
+        if a == b {
+            return false;
+        }
+
+        match self.rank[a].cmp(&self.rank[b]) {
+            std::cmp::Ordering::Less => self.parent[a] = b,
+            std::cmp::Ordering::Greater => self.parent[b] = a,
+            std::cmp::Ordering::Equal => {
+                self.parent[b] = a;
+                self.rank[a] += 1;
+            }
+        }
+
+        true
+    }
+}
+
+/// Fixed size set of the indexes `0..size` packed into a bit each, far cheaper than a `HashSet`
+/// when most of the indexes end up in the set, e.g. the visited cells of a generator
+#[derive(Debug, Clone, Default)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new(size: usize) -> Self {
+        Self {
+            words: vec![0; size.div_ceil(64)],
+        }
+    }
+
+    /// Add `index` to the set, returns `false` if it was already there
+    pub fn insert(&mut self, index: usize) -> bool {
+        let (word, bit) = (index / 64, 1 << (index % 64));
+        let added = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+
+        added
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        self.words[index / 64] &= !(1 << (index % 64));
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.words[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Number of indexes in the set
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+}
+
+/// Marks a node without a parent in the flat parent arrays of the searches
+pub const NO_PARENT: usize = usize::MAX;
+
+/// Follow the flat array of `parents` back from `end` to the start of a search, returning the
+/// route from the start
+pub fn route_to(parents: &[usize], end: usize) -> Vec<usize> {
+    let mut route = vec![end];
+
+    while let Some(&parent) = route.last().map(|node| &parents[*node]) {
+        if parent == NO_PARENT {
+            break;
+        }
+
+        route.push(parent);
+    }
+
+    route.reverse();
+    route
+}
+
+pub fn build_offset_getter<
+    T: PartialEq + Sub<u32, Output = T> + Add<u32, Output = T> + Sub<T, Output = T> + Copy,
+>(
+    min: (T, T),
+    max: (T, T),
+) -> impl Fn(T, T, Direction) -> Option<(T, T)> {
+    build_topology_offset_getter(min, max, Topology::Plane)
+}
+
+/// Same as `build_offset_getter` but steps off an edge wrap around to the other side when the
+/// `topology` allows it, see `Topology::step`
+pub fn build_topology_offset_getter<
+    T: PartialEq + Sub<u32, Output = T> + Add<u32, Output = T> + Sub<T, Output = T> + Copy,
+>(
     min: (T, T),
     max: (T, T),
+    topology: Topology,
 ) -> impl Fn(T, T, Direction) -> Option<(T, T)> {
+    let wrap_x = topology.wraps_horizontally() && max.0 - 1 != min.0;
+    let wrap_y = topology.wraps_vertically() && max.1 - 1 != min.1;
+    let wrapped_row = move |y: T| match topology {
+        Topology::Mobius => (max.1 - 1) - (y - min.1),
+        _ => y,
+    };
+
     move |x: T, y: T, dir: Direction| {
         // (x, y)
         let index_tuple: (T, T) = match dir {
             Direction::Top => {
                 if y == min.1 {
-                    return None;
+                    if !wrap_y {
+                        return None;
+                    }
+
+                    return Some((x, max.1 - 1));
                 }
 
                 (x, y - 1)
             }
             Direction::Right => {
                 if x == max.0 - 1 {
-                    return None;
+                    if !wrap_x {
+                        return None;
+                    }
+
+                    return Some((min.0, wrapped_row(y)));
                 }
 
                 (x + 1, y)
             }
             Direction::Bottom => {
                 if y == max.1 - 1 {
-                    return None;
+                    if !wrap_y {
+                        return None;
+                    }
+
+                    return Some((x, min.1));
                 }
 
                 (x, y + 1)
             }
             Direction::Left => {
                 if x == min.0 {
-                    return None;
+                    if !wrap_x {
+                        return None;
+                    }
+
+                    return Some((max.0 - 1, wrapped_row(y)));
                 }
 
                 (x - 1, y)
@@ -46,6 +208,30 @@ pub fn build_offset_getter<T: PartialEq + Sub<u32, Output = T> + Add<u32, Output
 mod test {
     use super::*;
 
+    #[test]
+    fn test_bitset_tracks_indexes() {
+        let mut set = BitSet::new(130);
+
+        assert!(set.insert(0));
+        assert!(set.insert(129));
+        assert!(!set.insert(129));
+        assert!(set.contains(129) && !set.contains(64));
+        assert_eq!(2, set.len());
+
+        set.remove(0);
+        set.remove(129);
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn test_route_follows_parents() {
+        // 3 came from 1, which came from 4, the start
+        let parents = [NO_PARENT, 4, NO_PARENT, 1, NO_PARENT];
+
+        assert_eq!(vec![4, 1, 3], route_to(&parents, 3));
+        assert_eq!(vec![2], route_to(&parents, 2));
+    }
+
     #[test]
     fn test_top_corner() {
         let get = build_offset_getter((0, 0), (10, 10));
@@ -55,11 +241,11 @@ mod test {
 
         let right = get(0, 0, Direction::Right);
         assert!(right.is_some());
-        assert_eq!((0, 1), right.unwrap());
+        assert_eq!((1, 0), right.unwrap());
 
         let bottom = get(0, 0, Direction::Bottom);
         assert!(bottom.is_some());
-        assert_eq!((1, 0), bottom.unwrap());
+        assert_eq!((0, 1), bottom.unwrap());
 
         let left = get(0, 0, Direction::Left);
         assert!(left.is_none());
@@ -70,15 +256,39 @@ mod test {
         let get = build_offset_getter((0, 0), (10, 10));
 
         let top = get(4, 4, Direction::Top);
-        assert_eq!((3, 4), top.unwrap());
+        assert_eq!((4, 3), top.unwrap());
 
         let right = get(4, 4, Direction::Right);
-        assert_eq!((4, 5), right.unwrap());
+        assert_eq!((5, 4), right.unwrap());
 
         let bottom = get(4, 4, Direction::Bottom);
-        assert_eq!((5, 4), bottom.unwrap());
+        assert_eq!((4, 5), bottom.unwrap());
 
         let left = get(4, 4, Direction::Left);
-        assert_eq!((4, 3), left.unwrap());
+        assert_eq!((3, 4), left.unwrap());
+    }
+
+    #[test]
+    fn test_cylinder_wraps_sides_only() {
+        let get = build_topology_offset_getter((0, 0), (10, 10), Topology::Cylinder);
+
+        assert_eq!((9, 3), get(0, 3, Direction::Left).unwrap());
+        assert_eq!((0, 3), get(9, 3, Direction::Right).unwrap());
+        assert!(get(4, 0, Direction::Top).is_none());
+        assert!(get(4, 9, Direction::Bottom).is_none());
+    }
+
+    #[test]
+    fn test_torus_and_mobius_wrap() {
+        let torus = build_topology_offset_getter((0, 0), (10, 10), Topology::Torus);
+
+        assert_eq!((4, 9), torus(4, 0, Direction::Top).unwrap());
+        assert_eq!((4, 0), torus(4, 9, Direction::Bottom).unwrap());
+
+        let mobius = build_topology_offset_getter((0, 0), (10, 10), Topology::Mobius);
+
+        assert_eq!((0, 7), mobius(9, 2, Direction::Right).unwrap());
+        assert_eq!((9, 7), mobius(0, 2, Direction::Left).unwrap());
+        assert!(mobius(4, 0, Direction::Top).is_none());
     }
 }
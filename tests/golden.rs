@@ -0,0 +1,253 @@
+//! Renders fixed seed mazes and compares them against the reference images in `tests/golden`.
+//!
+//! After a deliberate change to the renderers, regenerate the references with
+//! `UPDATE_GOLDENS=1 cargo test --test golden` and check the new images before committing them.
+//! When an image doesn't match, what was rendered and a diff with the changed pixels in red are
+//! written to `target/golden-diff`.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use maze_v1::algo::{
+    grid::{Grid, Topology},
+    maze::MazeGenerate,
+    weave::WeaveGrid,
+    RandomisedDFS, RandomisedKruskal,
+};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path};
+use maze_v1::render::{
+    colour::{ColourOptions, Gradient},
+    RenderOptions,
+};
+
+const SEED: u64 = 42;
+const DIFF_COLOUR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+fn golden_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden")
+}
+
+fn diff_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target/golden-diff")
+}
+
+fn updating() -> bool {
+    env::var_os("UPDATE_GOLDENS").is_some()
+}
+
+fn maze(rows: usize, cols: usize, topology: Topology) -> Grid {
+    let mut maze =
+        RandomisedDFS::from_grid(Grid::with_topology(rows, cols, topology)).with_seed(SEED);
+    maze.generate();
+    maze.grid
+}
+
+/// Route between opposite corners of the maze
+fn solution(grid: &Grid) -> Option<Vec<(usize, usize)>> {
+    let (start, end) = ((0, 0), (grid.cols - 1, grid.rows - 1));
+
+    GraphBuilder::<(usize, usize)>::from(grid)
+        .build()
+        .bfs(start, end)
+        .and_then(|path| walk_path(&path, &end))
+}
+
+/// The pixels of `expected` dimmed, with every pixel that differs from `actual` in red
+fn diff_image(expected: &RgbaImage, actual: &RgbaImage) -> RgbaImage {
+    let (width, height) = (
+        expected.width().max(actual.width()),
+        expected.height().max(actual.height()),
+    );
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        match (
+            expected.get_pixel_checked(x, y),
+            actual.get_pixel_checked(x, y),
+        ) {
+            (Some(e), Some(a)) if e == a => {
+                let grey = (e.0[0] as u16 + e.0[1] as u16 + e.0[2] as u16) / 3;
+                let grey = (128 + grey / 2) as u8;
+
+                Rgba([grey, grey, grey, 255])
+            }
+            _ => DIFF_COLOUR,
+        }
+    })
+}
+
+fn assert_image(name: &str, actual: RgbaImage) {
+    let path = golden_dir().join(format!("{}.png", name));
+
+    if updating() {
+        fs::create_dir_all(golden_dir()).unwrap();
+        actual.save(&path).unwrap();
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|err| {
+            panic!(
+                "can't open {:?}: {}, run with UPDATE_GOLDENS=1 to create it",
+                path, err
+            )
+        })
+        .into_rgba8();
+
+    if expected == actual {
+        return;
+    }
+
+    let diff = diff_image(&expected, &actual);
+    let changed = diff.pixels().filter(|p| **p == DIFF_COLOUR).count();
+
+    fs::create_dir_all(diff_dir()).unwrap();
+    actual
+        .save(diff_dir().join(format!("{}.actual.png", name)))
+        .unwrap();
+    diff.save(diff_dir().join(format!("{}.diff.png", name)))
+        .unwrap();
+
+    panic!(
+        "{} doesn't match its golden image, {} pixels differ, see {:?}",
+        name,
+        changed,
+        diff_dir()
+    );
+}
+
+fn assert_svg(name: &str, actual: String) {
+    let path = golden_dir().join(format!("{}.svg", name));
+
+    if updating() {
+        fs::create_dir_all(golden_dir()).unwrap();
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+
+    let expected = fs::read_to_string(&path).unwrap_or_else(|err| {
+        panic!(
+            "can't open {:?}: {}, run with UPDATE_GOLDENS=1 to create it",
+            path, err
+        )
+    });
+
+    if expected == actual {
+        return;
+    }
+
+    let line = expected
+        .lines()
+        .zip(actual.lines())
+        .position(|(e, a)| e != a)
+        .unwrap_or(expected.lines().count().min(actual.lines().count()));
+
+    fs::create_dir_all(diff_dir()).unwrap();
+    fs::write(diff_dir().join(format!("{}.actual.svg", name)), actual).unwrap();
+
+    panic!(
+        "{} doesn't match its golden SVG from line {}, see {:?}",
+        name,
+        line + 1,
+        diff_dir()
+    );
+}
+
+#[test]
+fn test_greyscale_images() {
+    for (name, topology) in [
+        ("plane", Topology::Plane),
+        ("torus", Topology::Torus),
+        ("mobius", Topology::Mobius),
+    ] {
+        let image = maze(8, 12, topology).generate_as_image();
+        assert_image(name, DynamicImage::ImageLuma8(image).into_rgba8());
+    }
+
+    let mut weave = RandomisedKruskal::from_grid(WeaveGrid::new(8, 12, 0.5)).with_seed(SEED);
+    weave.generate();
+    assert_image(
+        "weave",
+        DynamicImage::ImageLuma8(weave.grid.generate_as_image()).into_rgba8(),
+    );
+}
+
+#[test]
+fn test_colour_images() {
+    let grid = maze(8, 12, Topology::Plane);
+    let solved = RenderOptions {
+        cell_size: 6,
+        wall_width: 1,
+        entrance: Some((0, 0)),
+        exit: Some((11, 7)),
+        solution: solution(&grid),
+    };
+
+    let cases = [
+        (
+            "colour_default",
+            RenderOptions::default(),
+            ColourOptions::default(),
+        ),
+        (
+            "colour_thick_walls",
+            RenderOptions {
+                cell_size: 4,
+                wall_width: 5,
+                ..RenderOptions::default()
+            },
+            ColourOptions::default(),
+        ),
+        (
+            "colour_solution",
+            solved.clone(),
+            ColourOptions {
+                distances_from: Some((0, 0)),
+                gradient: Gradient::heat(),
+                dead_end: Some(Rgba([90, 90, 90, 255])),
+                ..ColourOptions::default()
+            },
+        ),
+    ];
+
+    for (name, options, colours) in cases {
+        assert_image(name, grid.generate_as_colour_image(&options, &colours));
+    }
+
+    // Openings across the wrapped edges are drawn as gaps in the border
+    let torus = maze(8, 12, Topology::Torus);
+    assert_image(
+        "colour_torus",
+        torus.generate_as_colour_image(
+            &RenderOptions {
+                solution: solution(&torus),
+                ..solved
+            },
+            &ColourOptions::default(),
+        ),
+    );
+}
+
+#[test]
+fn test_svgs() {
+    let grid = maze(8, 12, Topology::Plane);
+    assert_svg(
+        "svg_default",
+        grid.generate_as_svg(&RenderOptions::default()),
+    );
+
+    // The solution line is split where it crosses a wrapped edge
+    let torus = maze(8, 12, Topology::Torus);
+    assert_svg(
+        "svg_torus_solution",
+        torus.generate_as_svg(&RenderOptions {
+            cell_size: 16,
+            wall_width: 3,
+            entrance: Some((0, 0)),
+            exit: Some((11, 7)),
+            solution: solution(&torus),
+        }),
+    );
+}
@@ -0,0 +1,233 @@
+//! Invariants every generated maze should hold, checked on random sizes & seeds. A failing case
+//! is shrunk to the smallest size & seed that still fails and saved next to this file, so it's
+//! tried first on the next run.
+
+use proptest::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use maze_v1::algo::{
+    grid::{Grid, Topology},
+    maze::{Direction, MazeGenerate, MazeGrid},
+    weave::WeaveGrid,
+    ParallelTiled, RandomisedDFS, RandomisedKruskal,
+};
+use maze_v1::graph::{builder::GraphBuilder, graph::walk_path, indexed::IndexedGraph};
+
+type Point = (usize, usize);
+
+/// Every cell can reach every other cell
+fn assert_connected(builder: GraphBuilder<Point>, cells: usize) {
+    let graph = builder.build();
+
+    assert_eq!(cells, graph.len(), "cells missing from the graph");
+    assert_eq!(
+        1,
+        graph.connected_components().len(),
+        "maze isn't connected"
+    );
+}
+
+/// Connected with exactly `cells - 1` passages, so there's only one route between two cells
+fn assert_perfect(builder: GraphBuilder<Point>, cells: usize) {
+    let graph = builder.build();
+
+    assert_eq!(cells - 1, graph.edge_count());
+    assert_eq!(
+        1,
+        graph.connected_components().len(),
+        "maze isn't connected"
+    );
+}
+
+/// Each wall reads the same from both sides, and on a plane the outside is walled all the way
+/// round. A grid has no entrance of its own, the renderers mark one over the top. Walls into a
+/// weave crossing read as open from the tunnel's side only, `validate` checks the crossings.
+fn assert_walls(grid: &Grid) {
+    assert_eq!(Ok(()), grid.validate());
+
+    for cell in grid.cells() {
+        for dir in Direction::ALL {
+            match grid
+                .topology
+                .step(cell.x, cell.y, dir, (grid.cols, grid.rows))
+            {
+                Some((nx, ny)) if grid.is_under(nx, ny) || grid.is_under(cell.x, cell.y) => {}
+                Some((nx, ny)) => assert_eq!(
+                    grid.has_wall(cell.x, cell.y, dir),
+                    grid.has_wall(nx, ny, dir.opposite()),
+                    "wall on {:?} of {:?} differs from the other side",
+                    dir,
+                    (cell.x, cell.y)
+                ),
+                None => assert!(
+                    grid.has_wall(cell.x, cell.y, dir),
+                    "outside wall on {:?} of {:?} is open",
+                    dir,
+                    (cell.x, cell.y)
+                ),
+            }
+        }
+    }
+}
+
+/// `route` goes from `start` to `end` through open walls only
+fn assert_valid_route(grid: &Grid, route: &[Point], start: Point, end: Point) {
+    assert_eq!(Some(&start), route.first());
+    assert_eq!(Some(&end), route.last());
+
+    for step in route.windows(2) {
+        let ((x, y), next) = (step[0], step[1]);
+        let open = Direction::ALL.into_iter().any(|dir| {
+            grid.topology.step(x, y, dir, (grid.cols, grid.rows)) == Some(next)
+                && !grid.has_wall(x, y, dir)
+        });
+
+        assert!(open, "no passage from {:?} to {:?}", step[0], next);
+    }
+}
+
+fn size() -> impl Strategy<Value = (usize, usize)> {
+    (1usize..14, 1usize..14)
+}
+
+fn topology() -> impl Strategy<Value = Topology> {
+    prop_oneof![
+        Just(Topology::Plane),
+        Just(Topology::Cylinder),
+        Just(Topology::Torus),
+        Just(Topology::Mobius),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn dfs_makes_perfect_mazes((rows, cols) in size(), topology in topology(), seed: u64) {
+        let mut maze = RandomisedDFS::from_grid(Grid::with_topology(rows, cols, topology))
+            .with_seed(seed);
+        maze.generate();
+
+        assert_walls(&maze.grid);
+        assert_perfect(GraphBuilder::from(&maze.grid), rows * cols);
+    }
+
+    #[test]
+    fn kruskal_makes_perfect_mazes((rows, cols) in size(), topology in topology(), seed: u64) {
+        let mut maze = RandomisedKruskal::from_grid(Grid::with_topology(rows, cols, topology))
+            .with_seed(seed);
+        maze.generate();
+
+        assert_walls(&maze.grid);
+        assert_perfect(GraphBuilder::from(&maze.grid), rows * cols);
+    }
+
+    #[test]
+    fn parallel_makes_perfect_mazes(
+        (rows, cols) in size(),
+        tile_size in 1usize..6,
+        threads in 1usize..4,
+        seed: u64,
+    ) {
+        let mut maze = ParallelTiled::from_grid_size(rows, cols).with_seed(seed);
+        maze.tile_size = tile_size;
+        maze.threads = threads;
+        maze.generate();
+
+        assert_walls(&maze.grid);
+        assert_perfect(GraphBuilder::from(&maze.grid), rows * cols);
+    }
+
+    #[test]
+    fn weave_makes_perfect_mazes((rows, cols) in size(), density in 0.0..1.0f64, seed: u64) {
+        let mut maze = RandomisedKruskal::from_grid(WeaveGrid::new(rows, cols, density))
+            .with_seed(seed);
+        maze.generate();
+
+        assert_walls(&maze.grid.grid);
+        assert_perfect(GraphBuilder::from(&maze.grid), maze.grid.cell_count());
+    }
+
+    #[test]
+    fn braided_mazes_stay_connected((rows, cols) in size(), chance in 0.0..=1.0f64, seed: u64) {
+        let mut maze = RandomisedDFS::from_grid_size(rows, cols).with_seed(seed);
+        maze.generate();
+        maze.grid.braid_with(chance, &mut StdRng::seed_from_u64(seed));
+
+        assert_walls(&maze.grid);
+        assert_connected(GraphBuilder::from(&maze.grid), rows * cols);
+    }
+
+    #[test]
+    fn one_way_doors_keep_the_exit_reachable(
+        (rows, cols) in size(),
+        topology in topology(),
+        chance in 0.0..=1.0f64,
+        seed: u64,
+    ) {
+        let mut maze = RandomisedDFS::from_grid(Grid::with_topology(rows, cols, topology))
+            .with_seed(seed);
+        maze.generate();
+
+        let grid = &mut maze.grid;
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (start, end) = ((0, 0), (cols - 1, rows - 1));
+        grid.braid_with(chance, &mut rng);
+        grid.add_one_way_doors_with(chance, start, end, &mut rng);
+
+        assert_walls(grid);
+
+        // Directed searches from both ends agree on the route through the doors
+        let shortest = IndexedGraph::from(&*grid).bfs(0, rows * cols - 1).unwrap().len();
+        let graph = GraphBuilder::<Point>::from(&*grid).build();
+
+        for (name, path) in [
+            ("bfs", graph.bfs(start, end)),
+            ("bidirectional_bfs", graph.bidirectional_bfs(start, end)),
+        ] {
+            let route = path.and_then(|path| walk_path(&path, &end)).unwrap();
+
+            assert_eq!(shortest, route.len(), "{} route isn't the shortest", name);
+            assert!(
+                route.windows(2).all(|step| graph.weight(&step[0], &step[1]).is_some()),
+                "{} route goes the wrong way through a door",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn solvers_find_shortest_routes(
+        (rows, cols) in size(),
+        chance in 0.0..=1.0f64,
+        seed: u64,
+        (a, b) in (any::<usize>(), any::<usize>()),
+    ) {
+        let mut maze = RandomisedDFS::from_grid_size(rows, cols).with_seed(seed);
+        maze.generate();
+        maze.grid.braid_with(chance, &mut StdRng::seed_from_u64(seed));
+
+        let grid = &maze.grid;
+        let cells = rows * cols;
+        let (a, b) = (a % cells, b % cells);
+        let (start, end) = ((a % cols, a / cols), (b % cols, b / cols));
+
+        // Checked against the separate `IndexedGraph` BFS
+        let shortest = IndexedGraph::from(grid).distances(a)[b].unwrap();
+        let graph = GraphBuilder::<Point>::from(grid).build();
+
+        for (name, path) in [
+            ("bfs", graph.bfs(start, end)),
+            ("dijkstra", graph.dijkstra(start, end)),
+        ] {
+            let route = path.and_then(|path| walk_path(&path, &end)).unwrap();
+
+            assert_valid_route(grid, &route, start, end);
+            assert_eq!(shortest + 1, route.len(), "{} route isn't the shortest", name);
+        }
+
+        graph.astar(start, end, &|p1, p2| grid.manhattan_distance(*p1, *p2));
+        let route = walk_path(&graph.get_path(), &end).unwrap();
+
+        assert_valid_route(grid, &route, start, end);
+        assert_eq!(shortest + 1, route.len(), "astar route isn't the shortest");
+    }
+}